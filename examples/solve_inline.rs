@@ -0,0 +1,53 @@
+// Builds the official example input in code rather than reading it from a file, solves it with the
+// density strategy and prints the solution and its score. Run it with
+// `cargo run --example solve_inline`.
+
+extern crate hashcode;
+
+use hashcode::{solve_quietly, Cache, Endpoint, Input, RequestDescription, State, Strategy, Video};
+
+// A request description of `amount` requests with the default weight
+fn requests(amount: u32, video_id: usize, endpoint_id: usize) -> RequestDescription {
+    RequestDescription {
+        amount,
+        video_id,
+        endpoint_id,
+        weight: 1.0,
+    }
+}
+
+fn main() {
+    let videos = [50, 50, 80, 30, 110]
+        .iter()
+        .map(|&size| Video { size })
+        .collect();
+    let endpoints = vec![
+        // Cache connections are given fastest first
+        Endpoint {
+            latency: 1000,
+            cache_connections: vec![(0, 100), (2, 200), (1, 300)],
+        },
+        Endpoint {
+            latency: 500,
+            cache_connections: vec![],
+        },
+    ];
+    let caches = vec![Cache {}, Cache {}, Cache {}];
+    let request_descriptions = vec![
+        requests(1500, 3, 0),
+        requests(1000, 0, 1),
+        requests(500, 4, 0),
+        requests(1000, 1, 0),
+    ];
+    let input = Input::new(videos, endpoints, caches, 100, request_descriptions);
+
+    let state = solve_quietly(State::new(&input), Strategy::Density(0.0));
+    print!("{}", state.output());
+    let score = state.score();
+    println!("Score: {}", score);
+
+    // Videos 3 and 1 fit in cache 0 together, saving endpoint 0 900ms on 1500 and 1000 requests.
+    // Video 4 fits in no cache and endpoint 1 has none, so that's 2250000ms over 4000 requests.
+    assert_eq!(score.total_saved, 2_250_000);
+    assert_eq!(score.per_request_avg_x1000, 562_500);
+}
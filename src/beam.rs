@@ -0,0 +1,112 @@
+use std::cmp::Reverse;
+use std::collections::HashSet;
+use std::sync::atomic::Ordering;
+
+use rayon::prelude::*;
+
+use profile::{self, CANDIDATE_SCANS, REQUEST_DESCRIPTIONS};
+use {Id, State, INTERRUPTED};
+
+// How many of its best placements each state in the beam is expanded by
+const EXPANSION: usize = 4;
+
+// The `count` best (video, cache) placements by the same gain as `greedy_next`, best first
+fn best_placements(state: &State, count: usize) -> Vec<(Id, Id)> {
+    profile::count(&CANDIDATE_SCANS, 1);
+    profile::count(
+        &REQUEST_DESCRIPTIONS,
+        state.input.request_descriptions.len() as u64,
+    );
+    let mut placements: Vec<(u64, (Id, Id))> = state
+        .input
+        .request_descriptions
+        .par_iter()
+        .filter_map(|request_description| {
+            if state.is_caching(
+                request_description.endpoint_id,
+                request_description.video_id,
+            ) {
+                return None;
+            }
+
+            let endpoint = &state.input.endpoints[request_description.endpoint_id];
+            endpoint
+                .faster_connections()
+                .iter()
+                .find(|&&(cache_id, _)| state.fits(cache_id, request_description.video_id))
+                .map(|&(cache_id, cache_latency)| {
+                    (
+                        (endpoint.latency - cache_latency) as u64
+                            * request_description.weighted_amount(),
+                        (request_description.video_id, cache_id),
+                    )
+                })
+        })
+        .collect();
+    placements.sort_by_key(|&(gain, placement)| (Reverse(gain), placement));
+
+    let mut seen = HashSet::new();
+    placements
+        .into_iter()
+        .map(|(_, placement)| placement)
+        .filter(|&placement| seen.insert(placement))
+        .take(count)
+        .collect()
+}
+
+// Greedy placement that keeps the `beam_width` highest scoring partial states instead of just one.
+// Every state is expanded by its best few placements, and states that can't be extended any further
+// compete for the result. A width of one is plain greedy. The search starts from `initial`, whose
+// representation and limits every state in the beam shares.
+pub fn beam_search<'a>(initial: &State<'a>, beam_width: usize) -> State<'a> {
+    let mut beam = vec![initial.clone()];
+    let mut best = initial.clone();
+    let mut best_score = 0;
+
+    while !beam.is_empty() && !INTERRUPTED.load(Ordering::Relaxed) {
+        let mut children: Vec<(u128, State)> = Vec::new();
+        for state in beam.iter() {
+            let expansion = if beam_width == 1 { 1 } else { EXPANSION };
+            let placements = best_placements(state, expansion);
+            if placements.is_empty() {
+                let score = state.score().total_saved;
+                if score > best_score {
+                    best_score = score;
+                    best = state.clone();
+                }
+            }
+            for (video_id, cache_id) in placements {
+                let mut child = state.clone();
+                child.insert_video_in_cache(cache_id, video_id);
+                children.push((child.score().total_saved, child));
+            }
+        }
+
+        // The sort is stable, so ties keep the order the placements were ranked in
+        children.sort_by_key(|&(score, _)| Reverse(score));
+        beam = Vec::new();
+        for (_, child) in children {
+            if beam.len() == beam_width {
+                break;
+            }
+            // Placing the same videos in a different order reaches the same state
+            if !beam
+                .iter()
+                .any(|state: &State| state.cached_videos == child.cached_videos)
+            {
+                beam.push(child);
+            }
+        }
+    }
+
+    // An interrupted search still returns the best state it has reached
+    for state in beam {
+        let score = state.score().total_saved;
+        if score > best_score {
+            best_score = score;
+            best = state;
+        }
+    }
+
+    best
+}
@@ -0,0 +1,184 @@
+use std::collections::hash_set;
+use std::collections::HashSet;
+use std::mem::size_of;
+
+use Id;
+
+// How the videos held by each cache are stored
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StateRepr {
+    HashSet,
+    // One bit per video of the input, which is smaller and faster to probe as long as the number of
+    // videos is bounded
+    BitSet,
+}
+
+// One bit per video of the input
+#[derive(Debug, Clone, PartialEq)]
+pub struct BitSet {
+    words: Vec<u64>,
+    len: usize,
+}
+
+impl BitSet {
+    // An empty set for an input with `videos` videos
+    pub fn new(videos: usize) -> BitSet {
+        BitSet {
+            words: vec![0; videos.div_ceil(64)],
+            len: 0,
+        }
+    }
+
+    pub fn contains(&self, video_id: Id) -> bool {
+        self.words
+            .get(video_id / 64)
+            .is_some_and(|word| word & (1 << (video_id % 64)) != 0)
+    }
+
+    pub fn insert(&mut self, video_id: Id) -> bool {
+        let word = &mut self.words[video_id / 64];
+        let bit = 1 << (video_id % 64);
+        if *word & bit != 0 {
+            return false;
+        }
+        *word |= bit;
+        self.len += 1;
+        true
+    }
+
+    pub fn remove(&mut self, video_id: Id) -> bool {
+        let bit = 1 << (video_id % 64);
+        match self.words.get_mut(video_id / 64) {
+            Some(word) if *word & bit != 0 => {
+                *word &= !bit;
+                self.len -= 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    // The bytes the words take
+    pub fn heap_size(&self) -> usize {
+        self.words.capacity() * size_of::<u64>()
+    }
+
+    // The videos held, in ascending order
+    pub fn iter(&self) -> BitIter<'_> {
+        BitIter {
+            words: &self.words,
+            index: 0,
+            word: self.words.first().cloned().unwrap_or(0),
+        }
+    }
+}
+
+// The videos of a `BitSet`, found a word at a time from the lowest set bit
+pub struct BitIter<'a> {
+    words: &'a [u64],
+    // The word being scanned, with the bits already returned cleared
+    index: usize,
+    word: u64,
+}
+
+impl<'a> Iterator for BitIter<'a> {
+    type Item = Id;
+
+    fn next(&mut self) -> Option<Id> {
+        while self.word == 0 {
+            self.index += 1;
+            self.word = *self.words.get(self.index)?;
+        }
+        let bit = self.word.trailing_zeros() as usize;
+        self.word &= self.word - 1;
+        Some(self.index * 64 + bit)
+    }
+}
+
+// The videos held by one cache in the container chosen at runtime with `StateRepr`
+#[derive(Debug, Clone, PartialEq)]
+pub enum CachedVideos {
+    Hash(HashSet<Id>),
+    Bits(BitSet),
+}
+
+impl CachedVideos {
+    // An empty cache for an input with `videos` videos
+    pub fn new(repr: StateRepr, videos: usize) -> CachedVideos {
+        match repr {
+            StateRepr::HashSet => CachedVideos::Hash(HashSet::new()),
+            StateRepr::BitSet => CachedVideos::Bits(BitSet::new(videos)),
+        }
+    }
+
+    pub fn contains(&self, video_id: Id) -> bool {
+        match *self {
+            CachedVideos::Hash(ref videos) => videos.contains(&video_id),
+            CachedVideos::Bits(ref videos) => videos.contains(video_id),
+        }
+    }
+
+    // Adds `video_id`, returning whether it wasn't there already
+    pub fn insert(&mut self, video_id: Id) -> bool {
+        match *self {
+            CachedVideos::Hash(ref mut videos) => videos.insert(video_id),
+            CachedVideos::Bits(ref mut videos) => videos.insert(video_id),
+        }
+    }
+
+    // Removes `video_id`, returning whether it was there
+    pub fn remove(&mut self, video_id: Id) -> bool {
+        match *self {
+            CachedVideos::Hash(ref mut videos) => videos.remove(&video_id),
+            CachedVideos::Bits(ref mut videos) => videos.remove(video_id),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match *self {
+            CachedVideos::Hash(ref videos) => videos.len(),
+            CachedVideos::Bits(ref videos) => videos.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // Roughly the bytes the container has allocated, ignoring a hash table's control bytes
+    pub fn heap_size(&self) -> usize {
+        match *self {
+            CachedVideos::Hash(ref videos) => videos.capacity() * size_of::<Id>(),
+            CachedVideos::Bits(ref videos) => videos.heap_size(),
+        }
+    }
+
+    // The videos held, in arbitrary order for a hash set and ascending for a bitset
+    pub fn iter(&self) -> Iter<'_> {
+        match *self {
+            CachedVideos::Hash(ref videos) => Iter::Hash(videos.iter()),
+            CachedVideos::Bits(ref videos) => Iter::Bits(videos.iter()),
+        }
+    }
+}
+
+// The videos of a `CachedVideos`, iterated by whichever container holds them
+pub enum Iter<'a> {
+    Hash(hash_set::Iter<'a, Id>),
+    Bits(BitIter<'a>),
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = Id;
+
+    fn next(&mut self) -> Option<Id> {
+        match *self {
+            Iter::Hash(ref mut videos) => videos.next().cloned(),
+            Iter::Bits(ref mut videos) => videos.next(),
+        }
+    }
+}
@@ -0,0 +1,1157 @@
+// The command line: subcommands, flags and the reports they print around the solvers in the library
+
+use std::cmp::Reverse;
+use std::env;
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::io::{sink, stderr, BufWriter, IsTerminal, Write};
+use std::path::Path;
+use std::process;
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
+use ctrlc;
+use rayon;
+use rayon::prelude::*;
+
+use cached::StateRepr;
+use cluster::cache_clusters;
+use dot::to_dot;
+use generate::{generate_input, write_input};
+use improve::local_improve;
+use profile;
+use repl::repl;
+use scores::{checksum, ScoresKey};
+use solution::{parse_solution, repair_duplicates, score_solution, ValidationError};
+use {
+    baseline_score, density, density_candidates, fractional_bound, input_ratios, lns, load_input,
+    load_input_timed, naive_baseline, normalized_score, parse_and_score_stream, parse_failure,
+    parse_input, parse_preamble, parse_rank, parse_size, progress_bar, recommend_strategy,
+    request_volume, solve, upper_bound, Id, Input, InputFormat, PlacementLog, Score, State,
+    Strategy, EPSILON_WINDOW, FRACTIONAL_UNIT, INTERRUPTED,
+};
+
+// Prints the name and score of the highest scoring valid solution among `paths`. Invalid solutions
+// are reported and skipped, ties go to the first filename in lexicographic order.
+fn best(input: &Input, paths: &[String]) {
+    let mut scores: Vec<(&str, Score)> = Vec::new();
+
+    for path in paths {
+        let solution = match fs::read_to_string(path) {
+            Ok(solution) => solution,
+            Err(err) => {
+                eprintln!("warning: skipping {}: {}", path, err);
+                continue;
+            }
+        };
+
+        match score_solution(input, &solution) {
+            Ok(score) => scores.push((path, score)),
+            Err(errors) => {
+                eprintln!("warning: skipping invalid solution {}:", path);
+                for error in errors {
+                    eprintln!("    {}", error);
+                }
+            }
+        }
+    }
+
+    scores.sort_by_key(|&(path, score)| (Reverse(score), path));
+    if let Some((path, score)) = scores.first() {
+        println!("{} {}", path, score);
+    }
+}
+
+// The strategies `pareto` runs unless given others. Greedy and exact are left out as they take
+// minutes on the larger inputs.
+const PARETO_STRATEGIES: [Strategy; 8] = [
+    Strategy::Sorted,
+    Strategy::Coverage,
+    Strategy::Cluster,
+    Strategy::Density(0.0),
+    Strategy::Knapsack,
+    Strategy::Genetic,
+    Strategy::Video,
+    Strategy::Endpoint,
+];
+
+// Whether a strategy that scored `a` in the time `a_time` is at least as good as one that scored `b`
+// in `b_time` on both counts, and better on one of them
+fn dominates(a: Score, a_time: Duration, b: Score, b_time: Duration) -> bool {
+    a >= b && a_time <= b_time && (a > b || a_time < b_time)
+}
+
+// Solves `input` with each of `strategies`, printing the score and solve time of each and marking
+// with `*` the Pareto optimal ones, which no other strategy dominates. Strategies with the same score
+// and time are both optimal.
+fn pareto(input: &Input, strategies: &[Strategy]) {
+    let results: Vec<(Strategy, Score, Duration)> = strategies
+        .iter()
+        .map(|&strategy| {
+            let now = Instant::now();
+            let state = solve(
+                State::new(input),
+                strategy,
+                &mut PlacementLog::default(),
+                &mut progress_bar(input, sink()),
+            );
+            // Compared at the microseconds printed, so the marks can be checked from the report
+            let time = Duration::from_micros(now.elapsed().as_micros() as u64);
+            (strategy, state.score(), time)
+        })
+        .collect();
+
+    println!("{:<10} {:>10} {:>12}", "strategy", "score", "time");
+    for &(strategy, score, time) in results.iter() {
+        let optimal = !results
+            .iter()
+            .any(|&(_, other, other_time)| dominates(other, other_time, score, time));
+        println!(
+            "{:<10} {:>10} {:>10.3}ms{}",
+            strategy.to_string(),
+            score.per_request_avg_x1000,
+            time.as_micros() as f64 / 1000.0,
+            if optimal { " *" } else { "" }
+        );
+    }
+}
+
+// Places the videos of the solution at `path` in the empty `state`, exiting if the solution can't be
+// read or is invalid. Solvers only ever add videos, so these stay where they are.
+fn warm_start(state: &mut State, path: &str) {
+    let solution = fs::read_to_string(path).unwrap_or_else(|err| {
+        eprintln!("error: {}: {}", path, err);
+        process::exit(1);
+    });
+    let warm =
+        parse_solution(state.input, &solution).unwrap_or_else(|errors| exit_invalid(path, errors));
+
+    for (cache_id, videos) in warm.cached_videos.iter().enumerate() {
+        for video_id in videos.iter() {
+            state.insert_video_in_cache(cache_id, video_id);
+        }
+    }
+}
+
+// Reports why the solution at `path` is invalid and exits
+fn exit_invalid(path: &str, errors: Vec<ValidationError>) -> ! {
+    eprintln!("error: invalid solution {}:", path);
+    for error in errors {
+        eprintln!("    {}", error);
+    }
+    process::exit(1);
+}
+
+// Scores the solution at `path` against the input at `input_path` and prints the score. With
+// `expected`, exits non-zero unless the score matches it exactly. With `low_mem`, the request
+// descriptions are scored as they're read instead of being loaded up front. With `repair`, videos
+// listed twice for a cache are dropped first and the cleaned solution is written to that path.
+fn verify(
+    input_path: &str,
+    path: &str,
+    expected: Option<u64>,
+    low_mem: bool,
+    repair: Option<&str>,
+) {
+    let mut solution = fs::read_to_string(path).unwrap_or_else(|err| {
+        eprintln!("error: {}: {}", path, err);
+        process::exit(1);
+    });
+
+    if let Some(repaired_path) = repair {
+        let (repaired, removed) = repair_duplicates(&solution);
+        for duplicate in removed.iter() {
+            eprintln!("repaired: {}", duplicate);
+        }
+        fs::write(repaired_path, &repaired).unwrap_or_else(|err| {
+            eprintln!("error: cannot write {}: {}", repaired_path, err);
+            process::exit(1);
+        });
+        solution = repaired;
+    }
+
+    let score = if low_mem {
+        let (input, _, _) = parse_preamble(input_path, false).unwrap_or_else(|err| {
+            eprintln!("error: {}", parse_failure(input_path, &err));
+            process::exit(1);
+        });
+        let state = parse_solution(&input, &solution)
+            .unwrap_or_else(|errors| exit_invalid(path, errors));
+        parse_and_score_stream(input_path, &state).unwrap_or_else(|err| {
+            eprintln!("error: {}", parse_failure(input_path, &err));
+            process::exit(1);
+        })
+    } else {
+        score_solution(&load_input(input_path, 0.0), &solution)
+            .unwrap_or_else(|errors| exit_invalid(path, errors))
+    };
+
+    println!("{}", score);
+    if let Some(expected) = expected {
+        if score.per_request_avg_x1000 != expected {
+            eprintln!("error: expected a score of {} but got {}", expected, score);
+            process::exit(1);
+        }
+    }
+}
+
+// Prints the score of the solutions at `a` and `b` and, for every cache either one uses, the videos
+// only `a` places there, those only `b` places there and those both do
+fn compare(input: &Input, a: &str, b: &str) {
+    let load = |path: &str| -> State {
+        let solution = fs::read_to_string(path).unwrap_or_else(|err| {
+            eprintln!("error: {}: {}", path, err);
+            process::exit(1);
+        });
+        parse_solution(input, &solution).unwrap_or_else(|errors| exit_invalid(path, errors))
+    };
+    let (first, second) = (load(a), load(b));
+    println!("A {}: {}", a, first.score());
+    println!("B {}: {}", b, second.score());
+
+    for cache_id in 0..input.caches.len() {
+        let (in_a, in_b) = (&first.cached_videos[cache_id], &second.cached_videos[cache_id]);
+        if in_a.is_empty() && in_b.is_empty() {
+            continue;
+        }
+        let mut videos: Vec<Id> = in_a.iter().chain(in_b.iter()).collect();
+        videos.sort();
+        videos.dedup();
+        // The videos held as `(in_a, in_b)` says, or "-" if there are none
+        let list = |held: (bool, bool)| -> String {
+            let videos: Vec<String> = videos
+                .iter()
+                .filter(|&&video_id| (in_a.contains(video_id), in_b.contains(video_id)) == held)
+                .map(|video_id| video_id.to_string())
+                .collect();
+            if videos.is_empty() {
+                "-".to_string()
+            } else {
+                videos.join(" ")
+            }
+        };
+        println!(
+            "cache {}: only A: {}, only B: {}, both: {}",
+            cache_id,
+            list((true, false)),
+            list((false, true)),
+            list((true, true))
+        );
+    }
+}
+
+// Solves each of `paths` with `strategy`, writing every solution next to its input with an `.out`
+// extension, then prints a table of scores and times. Inputs that fail to parse are reported and
+// skipped.
+fn solve_batch(
+    paths: &[String],
+    strategy: Strategy,
+    repr: StateRepr,
+    max_videos_per_cache: Option<usize>,
+) {
+    let mut results = Vec::new();
+
+    for path in paths {
+        let now = Instant::now();
+        let input = match parse_input(path, false, 0.0) {
+            Ok(input) => input,
+            Err(err) => {
+                eprintln!("error: {}", parse_failure(path, &err));
+                results.push((path, Err(err.to_string())));
+                continue;
+            }
+        };
+
+        let mut state = State::with_repr(&input, repr);
+        state.max_videos_per_cache = max_videos_per_cache;
+        let state = solve(
+            state,
+            strategy,
+            &mut PlacementLog::default(),
+            &mut progress_bar(&input, sink()),
+        );
+        let output = Path::new(path).with_extension("out");
+        let written = File::create(&output).and_then(|file| {
+            let mut w = BufWriter::new(file);
+            state.write_output(&mut w)?;
+            w.flush()
+        });
+        let result = match written {
+            Ok(()) => Ok((state.score(), now.elapsed())),
+            Err(err) => Err(format!("cannot write {}: {}", output.display(), err)),
+        };
+        results.push((path, result));
+    }
+
+    // Best first, with the inputs that failed last
+    results.sort_by_key(|(_, result)| Reverse(result.as_ref().ok().map(|&(score, _)| score)));
+
+    println!(
+        "{:<32} {:>10} {:>16} {:>10}",
+        "input", "score", "saved", "time"
+    );
+    for (path, result) in results {
+        match result {
+            Ok((score, time)) => println!(
+                "{:<32} {:>10} {:>14}ms {:>8}ms",
+                path,
+                score.per_request_avg_x1000,
+                score.total_saved,
+                time.as_millis()
+            ),
+            Err(err) => println!("{:<32} failed: {}", path, err),
+        }
+    }
+}
+
+// Parses and solves each of the official inputs in `dir`, reporting the time spent in each phase
+// and the resulting score. Missing files are reported and skipped.
+fn bench<P: AsRef<Path>>(dir: P) {
+    let names = [
+        "example",
+        "me_at_the_zoo",
+        "videos_worth_spreading",
+        "trending_today",
+        "kittens",
+    ];
+    let mut total_score = 0;
+    let mut total_normalized = 0.0;
+    let mut solved = 0;
+    let mut total_parse = Duration::new(0, 0);
+    let mut total_solve = Duration::new(0, 0);
+
+    println!(
+        "{:<24} {:>10} {:>10} {:>10} {:>10}",
+        "input", "parse", "solve", "score", "normalized"
+    );
+
+    for name in names.iter() {
+        let path = dir.as_ref().join(format!("{}.in", name));
+        if !path.is_file() {
+            println!("{:<24} missing {}", name, path.display());
+            continue;
+        }
+
+        let now = Instant::now();
+        let input = match parse_input(&path, false, 0.0) {
+            Ok(input) => input,
+            Err(err) => {
+                println!("{:<24} error {}", name, err);
+                continue;
+            }
+        };
+        let parse_time = now.elapsed();
+
+        let now = Instant::now();
+        let mut state = State::new(&input);
+        density(
+            &mut state,
+            0.0,
+            &mut PlacementLog::default(),
+            &mut progress_bar(&input, sink()),
+        );
+        let solve_time = now.elapsed();
+
+        let score = state.score();
+        let normalized = normalized_score(&input, &score);
+        println!(
+            "{:<24} {:>8}ms {:>8}ms {:>10} {:>10.4}",
+            name,
+            parse_time.as_millis(),
+            solve_time.as_millis(),
+            score.per_request_avg_x1000,
+            normalized
+        );
+
+        total_score += score.per_request_avg_x1000;
+        total_normalized += normalized;
+        solved += 1;
+        total_parse += parse_time;
+        total_solve += solve_time;
+    }
+
+    // Normalized scores are averaged rather than summed, so the total stays between 0 and 1
+    println!(
+        "{:<24} {:>8}ms {:>8}ms {:>10} {:>10.4}",
+        "total",
+        total_parse.as_millis(),
+        total_solve.as_millis(),
+        total_score,
+        if solved == 0 {
+            0.0
+        } else {
+            total_normalized / solved as f64
+        }
+    );
+}
+
+// How often `state` places each video on average: a ratio near 1 spreads the caches over many videos,
+// a high one replicates a few of them
+fn print_redundancy(state: &State) {
+    let (distinct, slots) = state.redundancy();
+    eprintln!(
+        "Redundancy: {} distinct videos in {} slots ({:.2} copies each)",
+        distinct,
+        slots,
+        if distinct == 0 {
+            0.0
+        } else {
+            slots as f64 / distinct as f64
+        }
+    );
+}
+
+// Estimates of the memory held by the input and by `state`, for judging e.g. whether `--bitset` pays
+// off on an input
+fn print_mem_report(state: &State) {
+    let sizes = state.input.heap_sizes();
+    let parts: Vec<String> = sizes
+        .iter()
+        .map(|&(what, size)| format!("{} {}", what, size))
+        .collect();
+    eprintln!(
+        "Memory: input {} bytes ({}), state {} bytes",
+        sizes.iter().map(|&(_, size)| size).sum::<usize>(),
+        parts.join(", "),
+        state.heap_size()
+    );
+}
+
+// Exits after writing `what` to stdout failed. A pipe closed by its reader, e.g. `head`, got all it
+// wanted, so that exits quietly with the status of a process killed by SIGPIPE rather than as an
+// error.
+fn exit_on_write_error(what: &str, err: io::Error) -> ! {
+    if err.kind() == io::ErrorKind::BrokenPipe {
+        process::exit(141);
+    }
+    eprintln!("error: cannot write {}: {}", what, err);
+    process::exit(1);
+}
+
+// Writes `output` to stdout and flushes it, exiting as `exit_on_write_error` does if that fails
+fn print_or_exit(what: &str, output: &str) {
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    out.write_all(output.as_bytes())
+        .and_then(|()| out.flush())
+        .unwrap_or_else(|err| exit_on_write_error(what, err));
+}
+
+// The value following `name` on the command line, if present
+fn flag<'a>(args: &'a [String], name: &str) -> Option<&'a str> {
+    args.iter()
+        .position(|arg| arg == name)
+        .and_then(|i| args.get(i + 1))
+        .map(|value| value.as_str())
+}
+
+// Whether the switch `name` was given on the command line
+fn switch(args: &[String], name: &str) -> bool {
+    args.iter().any(|arg| arg == name)
+}
+
+// Prints how much of each cache, and of all caches together, the solution uses
+fn print_fill_rate(state: &State) {
+    let input = state.input;
+    let capacity = input.cache_size as u64;
+    let percentage = |used: u64, total: u64| {
+        if total == 0 {
+            0.0
+        } else {
+            used as f64 / total as f64 * 100.0
+        }
+    };
+
+    for cache_id in 0..input.caches.len() {
+        let used = state.cache_usage(cache_id);
+        eprintln!(
+            "Cache {}: {}/{}MB ({:.2}%)",
+            cache_id,
+            input.megabytes(used),
+            input.megabytes(capacity),
+            percentage(used, capacity)
+        );
+    }
+
+    let empty = (0..input.caches.len())
+        .filter(|&cache_id| state.cached_videos[cache_id].is_empty())
+        .count();
+    let used: u64 = state.cache_usage.iter().sum();
+    let total = capacity * input.caches.len() as u64;
+    eprintln!(
+        "Empty caches: {}\nUtilization: {}/{}MB ({:.2}%)",
+        empty,
+        input.megabytes(used),
+        input.megabytes(total),
+        percentage(used, total)
+    );
+}
+
+// Prints the `count` highest density candidates left out because their cache had no room for them
+fn print_top_unplaced(state: &State, count: usize) {
+    let (cache_latency_scores, _) = density_candidates(state.input, 0.0, 0);
+    let unplaced = cache_latency_scores
+        .iter()
+        .filter(|&&(cache_id, video_id, _)| {
+            !state.cached_videos[cache_id].contains(video_id) && !state.fits(cache_id, video_id)
+        })
+        .take(count);
+
+    eprintln!("Top unplaced candidates:");
+    for &(cache_id, video_id, score) in unplaced {
+        eprintln!(
+            "    cache {} video {} ({}MB, {} free): {}",
+            cache_id,
+            video_id,
+            state.input.megabytes(state.input.videos[video_id].size as u64),
+            state.input.megabytes(state.remaining_capacity(cache_id)),
+            score
+        );
+    }
+}
+
+// Prints what each placement of the solution serves and saves, as a log of its decisions that can
+// be checked against the input
+fn print_solution_explanation(state: &State) {
+    eprintln!("Placements by latency saved:");
+    for (cache_id, video_id, endpoint_ids, saved) in state.placement_savings() {
+        let endpoint_ids: Vec<String> = endpoint_ids.iter().map(|id| id.to_string()).collect();
+        eprintln!(
+            "    cache {} video {}: {}ms, endpoints {}",
+            cache_id,
+            video_id,
+            saved,
+            if endpoint_ids.is_empty() {
+                "none".to_string()
+            } else {
+                endpoint_ids.join(" ")
+            }
+        );
+    }
+}
+
+// Prints the request descriptions that save the most latency, to tell whether a few of them
+// dominate the score
+fn print_top_contributors(state: &State, count: usize) {
+    let total_saved = state.score().total_saved;
+    eprintln!("Top contributors:");
+    for (request_description, saved) in state.top_contributors(count) {
+        eprintln!(
+            "    video {} from endpoint {} ({} requests): {}ms ({:.2}%)",
+            request_description.video_id,
+            request_description.endpoint_id,
+            request_description.amount,
+            saved,
+            saved as f64 / total_saved as f64 * 100.0
+        );
+    }
+}
+
+// Prints the latency at which `endpoint_id` receives each video it requests, marking those served
+// from the datacenter
+fn print_endpoint_latencies(state: &State, endpoint_id: Id) {
+    let endpoint = match state.input.endpoints.get(endpoint_id) {
+        Some(endpoint) => endpoint,
+        None => {
+            eprintln!("error: unknown endpoint {}", endpoint_id);
+            process::exit(1);
+        }
+    };
+
+    eprintln!(
+        "Endpoint {} ({}ms to the datacenter, {}ms saved):",
+        endpoint_id,
+        endpoint.latency,
+        state.score_subset(&[endpoint_id]).total_saved
+    );
+    for (video_id, latency) in state.endpoint_effective_latency(endpoint_id) {
+        if latency == endpoint.latency {
+            eprintln!("    video {}: {}ms (datacenter)", video_id, latency);
+        } else {
+            eprintln!("    video {}: {}ms", video_id, latency);
+        }
+    }
+}
+
+pub fn main() {
+    let args: Vec<String> = env::args().collect();
+    if let Some(threads) = flag(&args, "--threads") {
+        let threads: usize = threads.parse().unwrap_or_else(|err| {
+            eprintln!("error: --threads {}: {}", threads, err);
+            process::exit(1);
+        });
+        // Zero leaves rayon's default of one thread per CPU
+        if threads > 0 {
+            rayon::initialize(rayon::Configuration::new().set_num_threads(threads)).unwrap();
+        }
+    }
+
+    if args[1] == "bench" {
+        bench(args.get(2).map_or("data", |dir| dir.as_str()));
+        return;
+    }
+    if args[1] == "best" {
+        best(&load_input(&args[2], 0.0), &args[3..]);
+        return;
+    }
+    if args[1] == "pareto" {
+        let strategies: Vec<Strategy> = if args.len() > 3 {
+            args[3..]
+                .iter()
+                .map(|name| {
+                    name.parse().unwrap_or_else(|err| {
+                        eprintln!("error: {}", err);
+                        process::exit(1);
+                    })
+                })
+                .collect()
+        } else {
+            PARETO_STRATEGIES.to_vec()
+        };
+        pareto(&load_input(&args[2], 0.0), &strategies);
+        return;
+    }
+    if args[1] == "compare" {
+        compare(&load_input(&args[2], 0.0), &args[3], &args[4]);
+        return;
+    }
+    if args[1] == "verify" {
+        let expected = flag(&args, "--expect").map(|expected| expected.parse().unwrap());
+        verify(
+            &args[2],
+            &args[3],
+            expected,
+            switch(&args, "--low-mem"),
+            flag(&args, "--repair"),
+        );
+        return;
+    }
+    if args[1] == "repl" {
+        let input = load_input(&args[2], 0.0);
+        let stdin = io::stdin();
+        repl(&input, stdin.lock(), io::stdout()).unwrap_or_else(|err| {
+            eprintln!("error: {}", err);
+            process::exit(1);
+        });
+        return;
+    }
+    if args[1] == "gen" {
+        let count = |i: usize| -> usize { args[i].parse().unwrap() };
+        let input = generate_input(
+            count(2),
+            count(3),
+            count(4),
+            count(5),
+            args[6].parse().unwrap(),
+            args.get(7).map_or(0, |seed| seed.parse().unwrap()),
+        );
+        print_or_exit("the input", &write_input(&input));
+        return;
+    }
+    if args[1] == "check" {
+        // Every file is checked even after a bad one, so one run reports all of them
+        let mut valid = true;
+        for path in args[2..].iter() {
+            match parse_input(path, false, 0.0) {
+                Ok(input) => println!(
+                    "{}: ok, {} videos, {} endpoints, {} caches, {} request descriptions",
+                    path,
+                    input.videos.len(),
+                    input.endpoints.len(),
+                    input.caches.len(),
+                    input.request_descriptions.len()
+                ),
+                Err(err) => {
+                    eprintln!("error: {}", parse_failure(path, &err));
+                    valid = false;
+                }
+            }
+        }
+        process::exit(if valid { 0 } else { 1 });
+    }
+    if args[1] == "dot" {
+        let input = load_input(&args[2], 0.0);
+        let max_nodes = flag(&args, "--max-nodes").map(|max| max.parse().unwrap());
+        print_or_exit("the graph", &to_dot(&input, max_nodes));
+        return;
+    }
+    if args[1] == "bound" {
+        let input = load_input(&args[2], 0.0);
+        if switch(&args, "--fractional") {
+            println!("{:.2}", fractional_bound(&input));
+        } else if switch(&args, "--baseline") {
+            println!(
+                "Requests: {}\nEmpty: {}\nNaive: {}",
+                request_volume(&input),
+                baseline_score(&input),
+                naive_baseline(&input).score()
+            );
+        } else {
+            println!("{}", upper_bound(&input));
+        }
+        return;
+    }
+
+    let mut strategy = flag(&args, "--strategy").map_or(Strategy::Density(0.0), |strategy| {
+        strategy.parse().unwrap_or_else(|err| {
+            eprintln!("error: {}", err);
+            process::exit(1);
+        })
+    });
+    if let Some(width) = flag(&args, "--beam") {
+        strategy = Strategy::Beam(width.parse().unwrap());
+    }
+    if let Some(fraction) = flag(&args, "--reserve") {
+        strategy = Strategy::Reserved(fraction.parse().unwrap());
+    }
+    if switch(&args, "--shuffle-endpoints") {
+        strategy = Strategy::ShuffledEndpoints(
+            flag(&args, "--seed").map_or(0, |seed| seed.parse().unwrap()),
+            flag(&args, "--trials").map_or(1, |trials| trials.parse().unwrap()),
+        );
+    }
+    let shuffled = matches!(strategy, Strategy::ShuffledEndpoints(..));
+    if let Some(weight) = flag(&args, "--coverage-weight") {
+        match strategy {
+            Strategy::Density(_) => strategy = Strategy::Density(weight.parse().unwrap()),
+            _ => {
+                eprintln!("error: --coverage-weight only applies to the density strategy");
+                process::exit(1);
+            }
+        }
+    }
+
+    let mut log = PlacementLog {
+        explain: flag(&args, "--explain").map_or(0, |count| count.parse().unwrap()),
+        ..PlacementLog::default()
+    };
+    if log.explain > 0 && !matches!(strategy, Strategy::Density(_)) {
+        eprintln!("error: --explain only applies to the density strategy");
+        process::exit(1);
+    }
+    log.prune = flag(&args, "--prune").map(|min_score| min_score.parse().unwrap());
+    if log.prune.is_some() && !matches!(strategy, Strategy::Density(_)) {
+        eprintln!("error: --prune only applies to the density strategy");
+        process::exit(1);
+    }
+    if let Some(name) = flag(&args, "--rank") {
+        if !matches!(strategy, Strategy::Density(_)) {
+            eprintln!("error: --rank only applies to the density strategy");
+            process::exit(1);
+        }
+        // Both adjust the density score, which a custom ranking replaces
+        if log.prune.is_some() || flag(&args, "--coverage-weight").is_some() {
+            eprintln!("error: --rank can't be combined with --prune or --coverage-weight");
+            process::exit(1);
+        }
+        log.rank = parse_rank(name).unwrap_or_else(|err| {
+            eprintln!("error: {}", err);
+            process::exit(1);
+        });
+    }
+    log.track_marginal = switch(&args, "--track-marginal");
+    if log.track_marginal {
+        if !matches!(strategy, Strategy::Density(_)) {
+            eprintln!("error: --track-marginal only applies to the density strategy");
+            process::exit(1);
+        }
+        // Both make the score something other than the latency saved per megabyte
+        if log.rank.is_some() || flag(&args, "--coverage-weight").is_some() {
+            eprintln!("error: --track-marginal can't be combined with --rank or --coverage-weight");
+            process::exit(1);
+        }
+    }
+    if let Some(epsilon) = flag(&args, "--epsilon") {
+        if !matches!(strategy, Strategy::Density(_)) {
+            eprintln!("error: --epsilon only applies to the density strategy");
+            process::exit(1);
+        }
+        let epsilon: f64 = epsilon.parse().unwrap();
+        if !(0.0..=1.0).contains(&epsilon) {
+            eprintln!("error: --epsilon must be between 0 and 1");
+            process::exit(1);
+        }
+        // These describe the placements of a single pass
+        if log.explain > 0 || log.track_marginal || flag(&args, "--csv").is_some() {
+            eprintln!("error: --epsilon can't be combined with --explain, --track-marginal or --csv");
+            process::exit(1);
+        }
+        // Its random picks walk the density order, which a custom ranking keeps changing
+        if log.rank.is_some() {
+            eprintln!("error: --epsilon can't be combined with --rank");
+            process::exit(1);
+        }
+        log.epsilon = Some(epsilon);
+        log.seed = flag(&args, "--seed").map_or(0, |seed| seed.parse().unwrap());
+        log.trials = flag(&args, "--trials").map_or(1, |trials| trials.parse().unwrap());
+        log.window = flag(&args, "--window")
+            .map_or(EPSILON_WINDOW, |window| window.parse().unwrap());
+        if log.window == 0 {
+            eprintln!("error: --window must be at least 1");
+            process::exit(1);
+        }
+    } else if flag(&args, "--window").is_some() {
+        eprintln!("error: --window only applies to --epsilon");
+        process::exit(1);
+    } else if flag(&args, "--trials").is_some() && !shuffled {
+        eprintln!("error: --trials only applies to --epsilon and --shuffle-endpoints");
+        process::exit(1);
+    }
+    log.heap = switch(&args, "--heap");
+    if log.heap && (!matches!(strategy, Strategy::Density(_)) || log.epsilon.is_some()) {
+        eprintln!("error: --heap only applies to the density strategy without --epsilon");
+        process::exit(1);
+    }
+    if let Some(path) = flag(&args, "--csv") {
+        if !matches!(strategy, Strategy::Density(_)) {
+            eprintln!("error: --csv only applies to the density strategy");
+            process::exit(1);
+        }
+        log.csv(path).unwrap_or_else(|err| {
+            eprintln!("error: cannot write {}: {}", path, err);
+            process::exit(1);
+        });
+    }
+    log.save_scores = flag(&args, "--save-scores").map(|path| path.to_string());
+    log.load_scores = flag(&args, "--load-scores").map(|path| path.to_string());
+    if log.save_scores.is_some() || log.load_scores.is_some() {
+        if !matches!(strategy, Strategy::Density(_)) {
+            eprintln!("error: --save-scores and --load-scores only apply to the density strategy");
+            process::exit(1);
+        }
+        let checksum = checksum(&args[1]).unwrap_or_else(|err| {
+            eprintln!("error: cannot read {}: {}", args[1], err);
+            process::exit(1);
+        });
+        log.scores_key = Some(ScoresKey {
+            checksum,
+            coverage_weight: match strategy {
+                Strategy::Density(coverage_weight) => coverage_weight,
+                _ => unreachable!(),
+            },
+            prune: log.prune.unwrap_or(0),
+            decay: flag(&args, "--decay").map_or(0.0, |decay| decay.parse().unwrap()),
+        });
+    }
+
+    let repr = if switch(&args, "--bitset") {
+        StateRepr::BitSet
+    } else {
+        StateRepr::HashSet
+    };
+
+    let max_videos_per_cache =
+        flag(&args, "--max-videos-per-cache").map(|max| max.parse().unwrap());
+    // LNS and hill climbing move videos around, which would undo a warm start
+    if switch(&args, "--warm-start") && (switch(&args, "--lns") || switch(&args, "--improve")) {
+        eprintln!("error: --warm-start can't be combined with --lns or --improve");
+        process::exit(1);
+    }
+    // Both pick the seeds of the randomized searches
+    if flag(&args, "--seed").is_some()
+        && !switch(&args, "--lns")
+        && log.epsilon.is_none()
+        && !shuffled
+    {
+        eprintln!("error: --seed only applies to --lns, --epsilon and --shuffle-endpoints");
+        process::exit(1);
+    }
+    if flag(&args, "--sweep-seeds").is_some() && !switch(&args, "--lns") {
+        eprintln!("error: --sweep-seeds only applies to --lns");
+        process::exit(1);
+    }
+    if flag(&args, "--seed").is_some() && flag(&args, "--sweep-seeds").is_some() {
+        eprintln!("error: --seed can't be combined with --sweep-seeds");
+        process::exit(1);
+    }
+
+    if args[1] == "solve" {
+        let paths: Vec<String> = args[2..]
+            .iter()
+            .take_while(|arg| !arg.starts_with("--"))
+            .cloned()
+            .collect();
+        solve_batch(&paths, strategy, repr, max_videos_per_cache);
+        return;
+    }
+
+    ctrlc::set_handler(|| INTERRUPTED.store(true, Ordering::Relaxed)).unwrap();
+    if switch(&args, "--profile") {
+        if !cfg!(feature = "profile") {
+            eprintln!("error: --profile needs a build with the profile feature");
+            process::exit(1);
+        }
+        profile::enable();
+    }
+
+    let decay = flag(&args, "--decay").map_or(0.0, |decay| decay.parse().unwrap());
+    let parsing = Instant::now();
+    let format = flag(&args, "--input-format").map_or(InputFormat::Flat, |format| {
+        format.parse().unwrap_or_else(|err| {
+            eprintln!("error: {}", err);
+            process::exit(1);
+        })
+    });
+    let (input, indexing) = load_input_timed(&args[1], format, decay);
+    let parsing = parsing.elapsed() - indexing;
+    if strategy == Strategy::Auto {
+        strategy = recommend_strategy(&input);
+        if switch(&args, "--verbose") {
+            let (size_ratio, connectivity) = input_ratios(&input);
+            eprintln!(
+                "Recommended strategy: {} (the average video fills {:.2}% of a cache, endpoints \
+                 reach {:.2}% of the caches on average)",
+                strategy,
+                size_ratio * 100.0,
+                connectivity * 100.0
+            );
+        }
+    }
+
+    // The bar redraws itself with control characters, which only make sense on a terminal unless
+    // `--progress` asks for them anyway
+    let handle: Box<dyn Write> = if switch(&args, "--no-progress")
+        || !(stderr().is_terminal() || switch(&args, "--progress"))
+    {
+        Box::new(sink())
+    } else {
+        Box::new(stderr())
+    };
+    let mut pb = progress_bar(&input, handle);
+    // In the input's size unit, rounded down so that the placement stays within it
+    let budget = flag(&args, "--budget").map(|budget| match parse_size(budget) {
+        Some(size) => size / (FRACTIONAL_UNIT / input.size_unit) as u64,
+        None => {
+            eprintln!("error: --budget must be a size in megabytes");
+            process::exit(1);
+        }
+    });
+    // Both place videos without checking them against the budget
+    if budget.is_some() && (flag(&args, "--load").is_some() || flag(&args, "--warm-start").is_some())
+    {
+        eprintln!("error: --budget can't be combined with --load or --warm-start");
+        process::exit(1);
+    }
+    let now = Instant::now();
+
+    let mut state = match flag(&args, "--load") {
+        Some(path) => State::load(&input, path).unwrap_or_else(|err| {
+            eprintln!("error: cannot load {}: {}", path, err);
+            process::exit(1);
+        }),
+        None => {
+            let mut state = State::with_repr(&input, repr);
+            state.max_videos_per_cache = max_videos_per_cache;
+            state.budget = budget;
+            if let Some(path) = flag(&args, "--warm-start") {
+                warm_start(&mut state, path);
+            }
+            let solved = solve(state.clone(), strategy, &mut log, &mut pb);
+            // A strategy that saves nothing where something could be saved, e.g. density when
+            // every score rounds to zero and is pruned, would submit a worthless solution, so
+            // greedy's placements are made instead by the sorted pass that computes them. The
+            // upper bound ignores cache sizes, so it also takes some video that fits.
+            if solved.score().total_saved == 0
+                && upper_bound(&input) > 0
+                && input.can_save_anything()
+                && !INTERRUPTED.load(Ordering::Relaxed)
+            {
+                eprintln!(
+                    "warning: the {} strategy saved nothing, falling back to sorted",
+                    strategy
+                );
+                strategy = Strategy::Sorted;
+                solve(state, strategy, &mut log, &mut pb)
+            } else {
+                solved
+            }
+        }
+    };
+    if let Some(mut csv) = log.csv.take() {
+        csv.flush().unwrap_or_else(|err| {
+            eprintln!("error: cannot write CSV: {}", err);
+            process::exit(1);
+        });
+    }
+
+    if let Some(iterations) = flag(&args, "--lns") {
+        let iterations = iterations.parse().unwrap();
+        let before = state.score();
+        let seed = match flag(&args, "--sweep-seeds") {
+            // Every seed searches from its own copy of the solution, and the best result is kept,
+            // the lowest seed among equal scores
+            Some(seeds) => {
+                let seeds: usize = seeds.parse().unwrap();
+                let results: Vec<(u128, u64, State)> = (0..seeds.max(1))
+                    .into_par_iter()
+                    .map(|seed| {
+                        let searched = lns(state.clone(), iterations, 0.1, seed as u64);
+                        (searched.score().total_saved, seed as u64, searched)
+                    })
+                    .collect();
+                let (_, seed, best) = results
+                    .into_iter()
+                    .max_by_key(|&(total_saved, seed, _)| (total_saved, Reverse(seed)))
+                    .unwrap();
+                state = best;
+                seed
+            }
+            None => {
+                let seed = flag(&args, "--seed").map_or(0, |seed| seed.parse().unwrap());
+                state = lns(state, iterations, 0.1, seed);
+                seed
+            }
+        };
+        let after = state.score();
+        eprintln!(
+            "\nLNS: {} -> {} (+{}) with seed {}",
+            before.per_request_avg_x1000,
+            after.per_request_avg_x1000,
+            after.per_request_avg_x1000 - before.per_request_avg_x1000,
+            seed
+        );
+    }
+
+    if switch(&args, "--improve") {
+        let before = state.score();
+        while local_improve(&mut state) {
+            if INTERRUPTED.load(Ordering::Relaxed) {
+                break;
+            }
+        }
+        let after = state.score();
+        eprintln!(
+            "\nImprove: {} -> {} (+{})",
+            before.per_request_avg_x1000,
+            after.per_request_avg_x1000,
+            after.per_request_avg_x1000 - before.per_request_avg_x1000
+        );
+    }
+
+    let solving = now.elapsed();
+    if switch(&args, "--assert-valid") {
+        if let Err(errors) = score_solution(&input, &state.output()) {
+            eprintln!("error: the {} strategy produced an invalid solution:", strategy);
+            for error in errors {
+                eprintln!("    {}", error);
+            }
+            process::exit(1);
+        }
+    }
+
+    let upper_bound = upper_bound(&input);
+    // Scoring scans every request description, so the summary is built from a single breakdown
+    let scoring = Instant::now();
+    let breakdown = state.score_breakdown();
+    let scoring = scoring.elapsed();
+    let score = Score::new(breakdown.sum_latency, breakdown.sum_requests);
+    eprintln!(
+        "\nStrategy: {}\nTime: {}s\nScore: {}\nNormalized: {:.4}\nTotal Saved: {}ms\nUpper Bound: {}ms",
+        strategy,
+        Instant::now().duration_since(now).as_secs(),
+        score.per_request_avg_x1000,
+        normalized_score(&input, &score),
+        score.total_saved,
+        upper_bound
+    );
+    eprintln!(
+        "Served: {} request descriptions, {} unserved ({:.2}% of requests), {} of {} endpoints",
+        breakdown.served,
+        breakdown.unserved,
+        if breakdown.sum_requests == 0 {
+            0.0
+        } else {
+            breakdown.served_requests as f64 / breakdown.sum_requests as f64 * 100.0
+        },
+        breakdown.served_endpoints,
+        input.endpoints.len()
+    );
+    if switch(&args, "--verbose") {
+        // A parsed input read as many request lines as its header declared, so any fewer request
+        // descriptions are duplicates that were merged
+        eprintln!(
+            "Requests: {} lines read, {} request descriptions after merging {} duplicates",
+            input.declared_requests,
+            input.request_descriptions.len(),
+            input.declared_requests - input.request_descriptions.len()
+        );
+        let unreachable: Vec<String> = input
+            .unreachable_caches()
+            .iter()
+            .map(|cache_id| cache_id.to_string())
+            .collect();
+        if !unreachable.is_empty() {
+            eprintln!(
+                "warning: no endpoint is connected to caches {}",
+                unreachable.join(" ")
+            );
+        }
+        let useless: Vec<String> = input
+            .useless_endpoints()
+            .iter()
+            .map(|endpoint_id| endpoint_id.to_string())
+            .collect();
+        if !useless.is_empty() {
+            eprintln!(
+                "warning: no cache is faster than the datacenter for endpoints {}",
+                useless.join(" ")
+            );
+        }
+        eprintln!(
+            "Phases: parse {:.2}s, indices {:.2}s, solve {:.2}s, score {:.2}s",
+            parsing.as_secs_f64(),
+            indexing.as_secs_f64(),
+            solving.as_secs_f64(),
+            scoring.as_secs_f64()
+        );
+        print_fill_rate(&state);
+        let sizes: Vec<String> = cache_clusters(&input)
+            .iter()
+            .map(|cluster| cluster.len().to_string())
+            .collect();
+        eprintln!("Cache clusters: {} ({})", sizes.len(), sizes.join(" "));
+    }
+    if switch(&args, "--profile") {
+        profile::print(solving);
+    }
+    if switch(&args, "--mem-report") {
+        print_mem_report(&state);
+    }
+    if switch(&args, "--report-redundancy") {
+        print_redundancy(&state);
+    }
+    if let Some(count) = flag(&args, "--top-k") {
+        print_top_unplaced(&state, count.parse().unwrap());
+    }
+    if switch(&args, "--explain-solution") {
+        print_solution_explanation(&state);
+    }
+    if let Some(count) = flag(&args, "--top-contributors") {
+        print_top_contributors(&state, count.parse().unwrap());
+    }
+    if let Some(endpoint_id) = flag(&args, "--endpoint") {
+        print_endpoint_latencies(&state, endpoint_id.parse().unwrap());
+    }
+    if let Some(path) = flag(&args, "--save") {
+        state.save(path).unwrap_or_else(|err| {
+            eprintln!("error: cannot save {}: {}", path, err);
+            process::exit(1);
+        });
+    }
+    if let Some(path) = flag(&args, "--annotate-file") {
+        fs::write(path, state.annotations()).unwrap_or_else(|err| {
+            eprintln!("error: cannot write {}: {}", path, err);
+            process::exit(1);
+        });
+    }
+    let stdout = io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+    let written = if switch(&args, "--nonempty") {
+        state.write_output_nonempty(&mut out)
+    } else {
+        state.write_output(&mut out)
+    };
+    written
+        .and_then(|()| out.flush())
+        .unwrap_or_else(|err| exit_on_write_error("the solution", err));
+}
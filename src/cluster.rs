@@ -0,0 +1,110 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::io::Write;
+use std::sync::atomic::Ordering;
+
+use pbr::ProgressBar;
+
+use coverage::requests_by_placement;
+use {Id, Input, State, INTERRUPTED};
+
+// Groups the caches into clusters that share endpoints, directly or through other caches. Placements
+// in one cluster never affect the latency of an endpoint served by another. Clusters are ordered by
+// their lowest cache id and list their caches in ascending order.
+pub fn cache_clusters(input: &Input) -> Vec<Vec<Id>> {
+    let mut visited = vec![false; input.caches.len()];
+    let mut clusters = Vec::new();
+
+    for start in 0..input.caches.len() {
+        if visited[start] {
+            continue;
+        }
+
+        visited[start] = true;
+        let mut cluster = vec![start];
+        let mut i = 0;
+        while i < cluster.len() {
+            let cache_id = cluster[i];
+            for &endpoint_id in input.endpoints_by_cache[cache_id].iter() {
+                for &(neighbor_id, _) in input.endpoints[endpoint_id].cache_connections.iter() {
+                    if !visited[neighbor_id] {
+                        visited[neighbor_id] = true;
+                        cluster.push(neighbor_id);
+                    }
+                }
+            }
+            i += 1;
+        }
+
+        cluster.sort();
+        clusters.push(cluster);
+    }
+
+    clusters
+}
+
+// The latency per megabyte a placement would save the endpoints that don't get the video from any
+// cache yet. `requests` are the request descriptions it could speed up, as `requests_by_placement`
+// lists them.
+fn breadth_gain(state: &State, video_id: Id, requests: &[(usize, u32)]) -> u64 {
+    let input = state.input;
+    let gain: u64 = requests
+        .iter()
+        .map(|&(i, cache_latency)| {
+            let request_description = &input.request_descriptions[i];
+            let endpoint = &input.endpoints[request_description.endpoint_id];
+            if state.latency(request_description.endpoint_id, video_id) < endpoint.latency {
+                0
+            } else {
+                endpoint.latency.saturating_sub(cache_latency) as u64
+                    * request_description.weighted_amount()
+            }
+        })
+        .sum();
+    gain / input.videos[video_id].size.max(1) as u64
+}
+
+// Density greedy that spends the capacity of each cluster on distinct videos. A copy of a video
+// already held elsewhere in the cluster is only worth what it saves endpoints that none of the
+// copies reach, so speeding up an endpoint that already gets the video from a slower cache counts
+// for nothing. Clusters are filled one at a time, with candidates in a heap re-evaluated lazily
+// like in `coverage_greedy`, as gains only shrink.
+pub fn cluster_aware_greedy<T: Write>(state: &mut State, pb: &mut ProgressBar<T>) {
+    let input = state.input;
+    let requests = requests_by_placement(input);
+    let mut placements: Vec<Vec<(Id, Id)>> = vec![Vec::new(); input.caches.len()];
+    for &(cache_id, video_id) in requests.keys() {
+        placements[cache_id].push((cache_id, video_id));
+    }
+
+    for cluster in cache_clusters(input) {
+        // Ties go to the lowest (cache, video) so the result doesn't depend on the HashMap's order
+        let mut heap: BinaryHeap<(u64, Reverse<(Id, Id)>)> = cluster
+            .iter()
+            .flat_map(|&cache_id| placements[cache_id].iter())
+            .map(|&(cache_id, video_id)| {
+                let gain = breadth_gain(state, video_id, &requests[&(cache_id, video_id)]);
+                (gain, Reverse((cache_id, video_id)))
+            })
+            .filter(|&(gain, _)| gain > 0)
+            .collect();
+
+        while let Some((gain, Reverse((cache_id, video_id)))) = heap.pop() {
+            if INTERRUPTED.load(Ordering::Relaxed) {
+                return;
+            }
+            if !state.fits(cache_id, video_id) || state.cached_videos[cache_id].contains(video_id) {
+                continue;
+            }
+            let current = breadth_gain(state, video_id, &requests[&(cache_id, video_id)]);
+            if current != gain {
+                if current > 0 {
+                    heap.push((current, Reverse((cache_id, video_id))));
+                }
+                continue;
+            }
+            state.insert_video_in_cache(cache_id, video_id);
+            pb.add(input.bytes(input.videos[video_id].size as u64));
+        }
+    }
+}
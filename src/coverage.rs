@@ -0,0 +1,96 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::io::Write;
+use std::sync::atomic::Ordering;
+
+use pbr::ProgressBar;
+
+use {Id, Input, State, INTERRUPTED};
+
+// The request descriptions a placement newly serves, the latency it saves, and the placement
+type Candidate = (usize, u64, Reverse<(Id, Id)>);
+
+// The request descriptions a placement would serve from the datacenter no longer, and the latency
+// it would save across all of them, given the current placement. `requests` holds the index and the
+// latency through the placement's cache of every request description it could speed up.
+fn coverage(state: &State, video_id: Id, requests: &[(usize, u32)]) -> (usize, u64) {
+    let mut newly_served = 0;
+    let mut gain = 0;
+    for &(i, cache_latency) in requests {
+        let request_description = &state.input.request_descriptions[i];
+        let endpoint = &state.input.endpoints[request_description.endpoint_id];
+        let latency = state.latency(request_description.endpoint_id, video_id);
+        if cache_latency < latency {
+            gain += (latency - cache_latency) as u64 * request_description.weighted_amount();
+            if latency == endpoint.latency {
+                newly_served += 1;
+            }
+        }
+    }
+    (newly_served, gain)
+}
+
+// The request descriptions each placement of a video in a cache could speed up, by their index and
+// the latency through that cache, leaving out videos too large for any cache
+pub fn requests_by_placement(input: &Input) -> HashMap<(Id, Id), Vec<(usize, u32)>> {
+    let mut requests: HashMap<(Id, Id), Vec<(usize, u32)>> = HashMap::new();
+    for (cache_id, endpoint_ids) in input.endpoints_by_cache.iter().enumerate() {
+        for &endpoint_id in endpoint_ids.iter() {
+            let &(_, cache_latency) = input.endpoints[endpoint_id]
+                .cache_connections
+                .iter()
+                .find(|&&(id, _)| id == cache_id)
+                .unwrap();
+            for &i in input.requests_by_endpoint[endpoint_id].iter() {
+                let video_id = input.request_descriptions[i].video_id;
+                if input.videos[video_id].size <= input.cache_size {
+                    requests
+                        .entry((cache_id, video_id))
+                        .or_default()
+                        .push((i, cache_latency));
+                }
+            }
+        }
+    }
+    requests
+}
+
+// Greedy on a lexicographic objective: every step makes the placement that moves the most request
+// descriptions from the datacenter to a cache, breaking ties by the latency saved. Both only ever
+// drop as videos get placed, so candidates are kept in a heap by a possibly stale value and only
+// re-evaluated when they reach the top, and one still at its value there is the best.
+pub fn coverage_greedy<T: Write>(state: &mut State, pb: &mut ProgressBar<T>) {
+    let input = state.input;
+    let requests = requests_by_placement(input);
+
+    // Ties go to the lowest (cache, video) so the result doesn't depend on the HashMap's order
+    let mut heap: BinaryHeap<Candidate> = requests
+        .iter()
+        .map(|(&(cache_id, video_id), requests)| {
+            let (newly_served, gain) = coverage(state, video_id, requests);
+            (newly_served, gain, Reverse((cache_id, video_id)))
+        })
+        .collect();
+
+    while let Some((newly_served, gain, Reverse((cache_id, video_id)))) = heap.pop() {
+        if INTERRUPTED.load(Ordering::Relaxed) {
+            return;
+        }
+        // A placement that doesn't fit now never will, as caches only fill up
+        if !state.fits(cache_id, video_id) || state.cached_videos[cache_id].contains(video_id) {
+            continue;
+        }
+        let current = coverage(state, video_id, &requests[&(cache_id, video_id)]);
+        if current != (newly_served, gain) {
+            if current.1 > 0 {
+                heap.push((current.0, current.1, Reverse((cache_id, video_id))));
+            }
+            continue;
+        }
+        if gain == 0 {
+            break;
+        }
+        state.insert_video_in_cache(cache_id, video_id);
+        pb.add(input.bytes(input.videos[video_id].size as u64));
+    }
+}
@@ -0,0 +1,79 @@
+use std::collections::HashSet;
+use std::fmt::Write;
+
+use {Id, Input};
+
+// The endpoint-cache graph of `input` in Graphviz DOT format: a node for the datacenter, each
+// endpoint and each cache, and an edge labeled with its latency for every connection, including
+// each endpoint's to the datacenter. With `max_nodes` only evenly spaced endpoints are drawn along
+// with the caches they're connected to, up to that many nodes in all, leaving out the connections to
+// caches that didn't make it.
+pub fn to_dot(input: &Input, max_nodes: Option<usize>) -> String {
+    let (endpoint_ids, cache_ids): (Vec<Id>, HashSet<Id>) = match max_nodes {
+        None => (
+            (0..input.endpoints.len()).collect(),
+            (0..input.caches.len()).collect(),
+        ),
+        Some(max_nodes) => {
+            let stride = (input.endpoints.len() / max_nodes.max(1)).max(1);
+            let mut endpoint_ids = Vec::new();
+            let mut cache_ids = HashSet::new();
+            // The datacenter is always drawn
+            let mut nodes = 1;
+            for endpoint_id in (0..input.endpoints.len()).step_by(stride) {
+                if nodes >= max_nodes {
+                    break;
+                }
+                endpoint_ids.push(endpoint_id);
+                nodes += 1;
+                for &(cache_id, _) in input.endpoints[endpoint_id].cache_connections.iter() {
+                    if nodes < max_nodes && cache_ids.insert(cache_id) {
+                        nodes += 1;
+                    }
+                }
+            }
+            (endpoint_ids, cache_ids)
+        }
+    };
+
+    let mut dot = String::from("graph hashcode {\n");
+    dot.push_str("    datacenter [shape=doublecircle];\n");
+    let mut sorted_cache_ids: Vec<Id> = cache_ids.iter().cloned().collect();
+    sorted_cache_ids.sort();
+    for cache_id in sorted_cache_ids {
+        writeln!(
+            dot,
+            "    c{} [shape=box, label=\"cache {}\"];",
+            cache_id, cache_id
+        )
+        .unwrap();
+    }
+    for &endpoint_id in endpoint_ids.iter() {
+        let endpoint = &input.endpoints[endpoint_id];
+        writeln!(
+            dot,
+            "    e{} [label=\"endpoint {}\"];",
+            endpoint_id, endpoint_id
+        )
+        .unwrap();
+        writeln!(
+            dot,
+            "    e{} -- datacenter [label=\"{}\"];",
+            endpoint_id, endpoint.latency
+        )
+        .unwrap();
+        for &(cache_id, latency) in endpoint.cache_connections.iter() {
+            if !cache_ids.contains(&cache_id) {
+                continue;
+            }
+            writeln!(
+                dot,
+                "    e{} -- c{} [label=\"{}\"];",
+                endpoint_id, cache_id, latency
+            )
+            .unwrap();
+        }
+    }
+    dot.push_str("}\n");
+    dot
+}
@@ -0,0 +1,73 @@
+use std::cmp::Reverse;
+use std::io::{sink, Write};
+use std::sync::atomic::Ordering;
+
+use pbr::ProgressBar;
+use rand::{Rng, SeedableRng, StdRng};
+
+use progress_bar;
+use {Id, Score, State, INTERRUPTED};
+
+// Greedy one endpoint at a time, in `order`: each endpoint's requests are taken by how many requests
+// they make per megabyte of their video, most first, and each video goes to the nearest cache of the
+// endpoint it still fits in unless the endpoint already gets it at least as fast. Unlike the global
+// greedies this only looks at one endpoint's requests per step, so the result depends on the order.
+pub fn endpoint_greedy<T: Write>(state: &mut State, order: &[Id], pb: &mut ProgressBar<T>) {
+    let input = state.input;
+    for &endpoint_id in order {
+        if INTERRUPTED.load(Ordering::Relaxed) {
+            return;
+        }
+        let mut requests = input.requests_by_endpoint[endpoint_id].clone();
+        requests.sort_by_key(|&i| {
+            let request_description = &input.request_descriptions[i];
+            let size = input.videos[request_description.video_id].size.max(1) as u64;
+            (
+                Reverse(request_description.weighted_amount() * 1000 / size),
+                request_description.video_id,
+            )
+        });
+
+        for i in requests {
+            let video_id = input.request_descriptions[i].video_id;
+            let cache_id = match state.best_cache_for(endpoint_id, video_id) {
+                Some(cache_id) => cache_id,
+                None => continue,
+            };
+            let &(_, cache_latency) = input.endpoints[endpoint_id]
+                .cache_connections
+                .iter()
+                .find(|&&(id, _)| id == cache_id)
+                .unwrap();
+            if cache_latency < state.latency(endpoint_id, video_id) {
+                state.insert_video_in_cache(cache_id, video_id);
+                pb.add(input.bytes(input.videos[video_id].size as u64));
+            }
+        }
+    }
+}
+
+// Runs `endpoint_greedy` on `trials` random orders of the endpoints, shuffled with the seeds from
+// `seed` on, and keeps the best result, the lowest seed among equal scores
+pub fn shuffled_endpoint_greedy(state: &mut State, seed: u64, trials: usize) {
+    let initial = state.clone();
+    let mut best: Option<(Score, u64)> = None;
+    for seed in seed..seed + trials.max(1) as u64 {
+        let mut order: Vec<Id> = (0..initial.input.endpoints.len()).collect();
+        StdRng::from_seed(&[seed as usize][..]).shuffle(&mut order);
+        let mut trial = initial.clone();
+        endpoint_greedy(&mut trial, &order, &mut progress_bar(initial.input, sink()));
+        let score = trial.score();
+        if best.is_none_or(|(best_score, _)| score > best_score) {
+            best = Some((score, seed));
+            *state = trial;
+        }
+    }
+    if let Some((_, seed)) = best {
+        eprintln!(
+            "Shuffled endpoints: best of {} trials with seed {}",
+            trials.max(1),
+            seed
+        );
+    }
+}
@@ -0,0 +1,162 @@
+use std::collections::{HashMap, HashSet};
+
+use {Id, State};
+
+// Branch and bound over which videos each cache holds, for inputs small enough to solve exactly.
+// Every (cache, video) placement that could save anything is decided in turn, placing the video
+// first, and a branch is cut once the latency saved so far plus a fractional bound on what the
+// undecided placements could add can't beat the best solution found. Returns that best solution,
+// built on top of `state`, or `None` if more than `max_states` branches would be searched.
+pub fn solve_exact<'a>(state: &State<'a>, max_states: u64) -> Option<State<'a>> {
+    let input = state.input;
+    // The videos each cache could serve faster than the datacenter, by id so the search is
+    // deterministic
+    let mut placements = Vec::new();
+    for cache_id in 0..input.caches.len() {
+        let mut videos: HashSet<Id> = HashSet::new();
+        for &endpoint_id in input.endpoints_by_cache[cache_id].iter() {
+            let endpoint = &input.endpoints[endpoint_id];
+            if !endpoint
+                .faster_connections()
+                .iter()
+                .any(|&(id, _)| id == cache_id)
+            {
+                continue;
+            }
+            for &i in input.requests_by_endpoint[endpoint_id].iter() {
+                let video_id = input.request_descriptions[i].video_id;
+                if input.videos[video_id].size <= input.cache_size
+                    && !state.cached_videos[cache_id].contains(video_id)
+                {
+                    videos.insert(video_id);
+                }
+            }
+        }
+        let mut videos: Vec<Id> = videos.into_iter().collect();
+        videos.sort();
+        placements.extend(videos.into_iter().map(|video_id| (cache_id, video_id)));
+    }
+
+    let mut search = Search {
+        placements: &placements,
+        best: state.clone(),
+        best_saved: state.score().total_saved,
+        states: 0,
+        max_states,
+    };
+    let mut current = state.clone();
+    if search.branch(&mut current, 0) {
+        Some(search.best)
+    } else {
+        None
+    }
+}
+
+struct Search<'a, 'b> {
+    placements: &'b [(Id, Id)],
+    best: State<'a>,
+    best_saved: u128,
+    states: u64,
+    max_states: u64,
+}
+
+impl<'a, 'b> Search<'a, 'b> {
+    // Decides the placements from `next` on, returning false once the search grows past
+    // `max_states`
+    fn branch(&mut self, state: &mut State<'a>, next: usize) -> bool {
+        self.states += 1;
+        if self.states > self.max_states {
+            return false;
+        }
+        let saved = state.score().total_saved;
+        if saved > self.best_saved {
+            self.best_saved = saved;
+            self.best = state.clone();
+        }
+        if next == self.placements.len() {
+            return true;
+        }
+        // Latency saved only ever adds up to whole milliseconds, so a bound below the best plus one
+        // can't lead anywhere better
+        if saved as f64 + self.remaining_bound(state, next) < self.best_saved as f64 + 1.0 {
+            return true;
+        }
+
+        let (cache_id, video_id) = self.placements[next];
+        if state.fits(cache_id, video_id) {
+            state.insert_video_in_cache(cache_id, video_id);
+            let finished = self.branch(state, next + 1);
+            state.remove_video_from_cache(cache_id, video_id);
+            if !finished {
+                return false;
+            }
+        }
+        self.branch(state, next + 1)
+    }
+
+    // The most the placements from `next` on could add to `state`: each cache filled on its own
+    // with a fractional knapsack of what its undecided videos would save given `state`. Savings
+    // only shrink as videos get placed and endpoints are counted for every cache they reach, so
+    // nothing they place together saves more.
+    fn remaining_bound(&self, state: &State, next: usize) -> f64 {
+        let input = state.input;
+        let mut by_cache: HashMap<Id, Vec<Id>> = HashMap::new();
+        for &(cache_id, video_id) in self.placements[next..].iter() {
+            by_cache.entry(cache_id).or_default().push(video_id);
+        }
+
+        let mut bound = 0.0;
+        for (cache_id, videos) in by_cache {
+            let mut values: Vec<(f64, u32, u64)> = videos
+                .into_iter()
+                .map(|video_id| {
+                    let value = gain(state, cache_id, video_id);
+                    let size = input.videos[video_id].size;
+                    (value as f64 / size as f64, size, value)
+                })
+                .filter(|&(_, _, value)| value > 0)
+                .collect();
+            values.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap());
+
+            let mut capacity = state.remaining_capacity(cache_id) as f64;
+            for (_, size, value) in values {
+                if size as f64 <= capacity {
+                    capacity -= size as f64;
+                    bound += value as f64;
+                } else {
+                    bound += value as f64 * capacity / size as f64;
+                    break;
+                }
+            }
+        }
+        bound
+    }
+}
+
+// The latency placing `video_id` in `cache_id` would save given `state`
+fn gain(state: &State, cache_id: Id, video_id: Id) -> u64 {
+    let input = state.input;
+    input.endpoints_by_cache[cache_id]
+        .iter()
+        .map(|&endpoint_id| {
+            let endpoint = &input.endpoints[endpoint_id];
+            let &(_, cache_latency) = endpoint
+                .cache_connections
+                .iter()
+                .find(|&&(id, _)| id == cache_id)
+                .unwrap();
+            let latency = state.latency(endpoint_id, video_id);
+            if cache_latency >= latency {
+                return 0;
+            }
+            input.requests_by_endpoint[endpoint_id]
+                .iter()
+                .map(|&i| &input.request_descriptions[i])
+                .filter(|request_description| request_description.video_id == video_id)
+                .map(|request_description| {
+                    (latency - cache_latency) as u64 * request_description.weighted_amount()
+                })
+                .sum::<u64>()
+        })
+        .sum()
+}
@@ -0,0 +1,109 @@
+use std::collections::HashSet;
+
+use rand::{self, Rng, SeedableRng, StdRng};
+
+use {Cache, Endpoint, Input, RequestDescription, Video};
+
+// Generates a random, well-formed input with the given dimensions. Every cache latency is below its
+// endpoint's datacenter latency and request descriptions are distinct (video, endpoint) pairs, so
+// the result survives a round-trip through `write_input` and `parse_input` unchanged. Fewer
+// requests than asked for are generated if there aren't enough distinct pairs.
+pub fn generate_input(
+    videos: usize,
+    endpoints: usize,
+    caches: usize,
+    requests: usize,
+    cache_size: u32,
+    seed: u64,
+) -> Input {
+    let mut rng = StdRng::from_seed(&[seed as usize][..]);
+
+    let videos: Vec<Video> = (0..videos)
+        .map(|_| Video {
+            size: rng.gen_range(1, 1001),
+        })
+        .collect();
+
+    let endpoints: Vec<Endpoint> = (0..endpoints)
+        .map(|_| {
+            let latency = rng.gen_range(2, 4001);
+            let connected = rng.gen_range(0, caches + 1);
+            let mut cache_connections: Vec<(usize, u32)> =
+                rand::sample(&mut rng, 0..caches, connected)
+                    .into_iter()
+                    .map(|cache_id| (cache_id, rng.gen_range(1, latency)))
+                    .collect();
+            cache_connections.sort_by_key(|a| a.1);
+            Endpoint {
+                latency,
+                cache_connections,
+            }
+        })
+        .collect();
+
+    let requests = requests.min(videos.len() * endpoints.len());
+    let mut seen = HashSet::new();
+    let mut request_descriptions = Vec::with_capacity(requests);
+    while request_descriptions.len() < requests {
+        let video_id = rng.gen_range(0, videos.len());
+        let endpoint_id = rng.gen_range(0, endpoints.len());
+        if seen.insert((video_id, endpoint_id)) {
+            request_descriptions.push(RequestDescription {
+                amount: rng.gen_range(1, 10_001),
+                video_id,
+                endpoint_id,
+                weight: 1.0,
+            });
+        }
+    }
+
+    Input::new(
+        videos,
+        endpoints,
+        (0..caches).map(|_| Cache {}).collect(),
+        cache_size,
+        request_descriptions,
+    )
+}
+
+// Writes `input` in the contest input format
+pub fn write_input(input: &Input) -> String {
+    let mut buffer = format!(
+        "{} {} {} {} {}\n",
+        input.videos.len(),
+        input.endpoints.len(),
+        input.request_descriptions.len(),
+        input.caches.len(),
+        input.megabytes(input.cache_size as u64)
+    );
+
+    let sizes: Vec<String> = input
+        .videos
+        .iter()
+        .map(|video| input.megabytes(video.size as u64).to_string())
+        .collect();
+    buffer.push_str(&sizes.join(" "));
+    buffer.push('\n');
+
+    for endpoint in input.endpoints.iter() {
+        buffer.push_str(&format!(
+            "{} {}\n",
+            endpoint.latency,
+            endpoint.cache_connections.len()
+        ));
+        for &(cache_id, latency) in endpoint.cache_connections.iter() {
+            buffer.push_str(&format!("{} {}\n", cache_id, latency));
+        }
+    }
+
+    for request_description in input.request_descriptions.iter() {
+        buffer.push_str(&format!(
+            "{} {} {}\n",
+            request_description.video_id,
+            request_description.endpoint_id,
+            request_description.amount
+        ));
+    }
+
+    buffer
+}
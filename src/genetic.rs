@@ -0,0 +1,107 @@
+use std::cmp::Reverse;
+use std::io::sink;
+use std::sync::atomic::Ordering;
+
+use rand::{Rng, SeedableRng, StdRng};
+
+use {density_candidates, density_fill, progress_bar, sort_candidates};
+use {Id, PlacementLog, Score, State, INTERRUPTED};
+
+// Individuals kept from one generation to the next
+const POPULATION: usize = 8;
+
+// Children bred before the best individual is returned
+const GENERATIONS: usize = 40;
+
+// A child of `a` and `b`: each cache takes the videos of one of the parents, picked at random, then
+// the videos the other parent holds there while they fit. Neither parent's choice can overfill a
+// cache, so the child is feasible whenever both parents are, except that caches taken from different
+// parents can add up to more than a `--budget`, so any video past it is left out.
+pub fn crossover<'a>(a: &State<'a>, b: &State<'a>, seed: u64) -> State<'a> {
+    let mut rng = StdRng::from_seed(&[seed as usize][..]);
+    let mut child = a.clone();
+
+    for cache_id in 0..a.input.caches.len() {
+        let (first, second) = if rng.gen() { (a, b) } else { (b, a) };
+        child.clear_cache(cache_id);
+        // Sorted so that the child only depends on the seed
+        let mut videos: Vec<Id> = first.cached_videos[cache_id].iter().collect();
+        videos.sort();
+        for video_id in videos {
+            let _ = child.try_insert_video_in_cache(cache_id, video_id);
+        }
+        let mut videos: Vec<Id> = second.cached_videos[cache_id].iter().collect();
+        videos.sort();
+        for video_id in videos {
+            if !child.cached_videos[cache_id].contains(video_id) {
+                let _ = child.try_insert_video_in_cache(cache_id, video_id);
+            }
+        }
+    }
+
+    child
+}
+
+// A genetic search seeded with the density greedy: the population starts from that solution and
+// from density fills with randomly scaled scores, then each generation breeds two individuals picked
+// by tournament and lets the child replace the worst individual if it scores higher.
+pub fn genetic(state: &mut State) {
+    let input = state.input;
+    let (cache_latency_scores, candidate_endpoints) = density_candidates(input, 0.0, 0);
+    let mut rng = StdRng::from_seed(&[0][..]);
+
+    let mut population = Vec::with_capacity(POPULATION);
+    for i in 0..POPULATION {
+        let mut candidates = cache_latency_scores.clone();
+        // The first individual is the density greedy's own solution
+        if i > 0 {
+            for candidate in candidates.iter_mut() {
+                candidate.2 = (candidate.2 as f64 * rng.gen_range(0.5, 1.5)) as u64;
+            }
+            sort_candidates(input, &mut candidates);
+        }
+        let mut individual = state.clone();
+        density_fill(
+            &mut individual,
+            &candidates,
+            &candidate_endpoints,
+            &mut PlacementLog::default(),
+            &mut progress_bar(input, sink()),
+        );
+        let score = individual.score();
+        population.push((score, individual));
+    }
+
+    for _ in 0..GENERATIONS {
+        if INTERRUPTED.load(Ordering::Relaxed) {
+            break;
+        }
+        let a = tournament(&population, &mut rng);
+        let b = tournament(&population, &mut rng);
+        let child = crossover(&population[a].1, &population[b].1, rng.gen());
+        let score = child.score();
+        let worst = (0..population.len())
+            .min_by_key(|&i| population[i].0)
+            .unwrap();
+        if score > population[worst].0 {
+            population[worst] = (score, child);
+        }
+    }
+
+    // Ties go to the earliest individual, so the density solution wins unless something beat it
+    let best = (0..population.len())
+        .max_by_key(|&i| (population[i].0, Reverse(i)))
+        .unwrap();
+    *state = population.swap_remove(best).1;
+}
+
+// The index of the better of two individuals picked at random
+fn tournament<R: Rng>(population: &[(Score, State)], rng: &mut R) -> usize {
+    let a = rng.gen_range(0, population.len());
+    let b = rng.gen_range(0, population.len());
+    if population[b].0 > population[a].0 {
+        b
+    } else {
+        a
+    }
+}
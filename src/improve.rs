@@ -0,0 +1,105 @@
+use std::collections::HashSet;
+
+use {Id, State};
+
+// One sweep of hill climbing. Every cached video is tried in each other cache that could serve one
+// of its requests, either moved there if it fits or swapped with a video already there. Changes
+// are kept only if they strictly increase the latency saved, so the score never drops and no cache
+// is ever overfilled. Returns whether anything changed.
+pub fn local_improve(state: &mut State) -> bool {
+    let input = state.input;
+    let mut improved = false;
+
+    for cache_id in 0..input.caches.len() {
+        let mut videos: Vec<Id> = state.cached_videos[cache_id].iter().collect();
+        videos.sort();
+
+        for video_id in videos {
+            // An earlier swap may already have moved this video out
+            if !state.cached_videos[cache_id].contains(video_id) {
+                continue;
+            }
+
+            let mut targets: Vec<Id> = input.requests_by_video[video_id]
+                .iter()
+                .flat_map(|&i| {
+                    let endpoint_id = input.request_descriptions[i].endpoint_id;
+                    input.endpoints[endpoint_id]
+                        .cache_connections
+                        .iter()
+                        .map(|&(target_id, _)| target_id)
+                })
+                .filter(|&target_id| {
+                    target_id != cache_id && !state.cached_videos[target_id].contains(video_id)
+                })
+                .collect::<HashSet<Id>>()
+                .into_iter()
+                .collect();
+            targets.sort();
+
+            for target_id in targets {
+                if relocate(state, video_id, cache_id, target_id)
+                    || swap(state, video_id, cache_id, target_id)
+                {
+                    improved = true;
+                    break;
+                }
+            }
+        }
+    }
+
+    improved
+}
+
+// Moves `video_id` from `from` to `to` if it fits there and saves more latency
+fn relocate(state: &mut State, video_id: Id, from: Id, to: Id) -> bool {
+    if !state.fits(to, video_id) {
+        return false;
+    }
+
+    let before = state.video_saving(video_id);
+    state.remove_video_from_cache(from, video_id);
+    state.try_insert_video_in_cache(to, video_id).unwrap();
+    if state.video_saving(video_id) > before {
+        return true;
+    }
+
+    state.remove_video_from_cache(to, video_id);
+    state.insert_video_in_cache(from, video_id);
+    false
+}
+
+// Exchanges `video_id` in `from` with the first video in `to` for which both caches still have room
+// afterwards and the pair saves more latency
+fn swap(state: &mut State, video_id: Id, from: Id, to: Id) -> bool {
+    let input = state.input;
+    let size = input.videos[video_id].size as u64;
+    let mut partners: Vec<Id> = state.cached_videos[to]
+        .iter()
+        .filter(|&other_id| {
+            let other_size = input.videos[other_id].size as u64;
+            !state.cached_videos[from].contains(other_id)
+                && size <= state.remaining_capacity(to) + other_size
+                && other_size <= state.remaining_capacity(from) + size
+        })
+        .collect();
+    partners.sort();
+
+    for other_id in partners {
+        let before = state.video_saving(video_id) + state.video_saving(other_id);
+        state.remove_video_from_cache(from, video_id);
+        state.remove_video_from_cache(to, other_id);
+        state.try_insert_video_in_cache(to, video_id).unwrap();
+        state.try_insert_video_in_cache(from, other_id).unwrap();
+        if state.video_saving(video_id) + state.video_saving(other_id) > before {
+            return true;
+        }
+
+        state.remove_video_from_cache(to, video_id);
+        state.remove_video_from_cache(from, other_id);
+        state.insert_video_in_cache(from, video_id);
+        state.insert_video_in_cache(to, other_id);
+    }
+
+    false
+}
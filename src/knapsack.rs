@@ -0,0 +1,81 @@
+use std::collections::HashMap;
+use std::io::Write;
+
+use pbr::ProgressBar;
+
+use {Id, State};
+
+// Capacities are bucketed so the dynamic programming table has at most this many columns. Sizes are
+// rounded up to whole buckets, so a chosen set always fits the real capacity.
+const MAX_BUCKETS: u32 = 10_000;
+
+// Fills the caches one at a time, each with the set of videos that maximizes the latency saved on
+// top of the caches filled before it, solved as a 0/1 knapsack over the remaining capacity.
+pub fn knapsack<T: Write>(state: &mut State, pb: &mut ProgressBar<T>) {
+    let input = state.input;
+
+    for cache_id in 0..input.caches.len() {
+        // A cache no endpoint reaches has nothing to gain from any video
+        if input.endpoints_by_cache[cache_id].is_empty() {
+            continue;
+        }
+
+        let capacity = state.remaining_capacity(cache_id) as u32;
+        let scale = capacity.div_ceil(MAX_BUCKETS).max(1);
+        let buckets = (capacity / scale) as usize;
+
+        let mut values: HashMap<Id, u64> = HashMap::new();
+        for &endpoint_id in input.endpoints_by_cache[cache_id].iter() {
+            let cache_latency = input.endpoints[endpoint_id]
+                .cache_connections
+                .iter()
+                .find(|&&(id, _)| id == cache_id)
+                .map(|&(_, latency)| latency)
+                .unwrap();
+
+            for &i in input.requests_by_endpoint[endpoint_id].iter() {
+                let request_description = &input.request_descriptions[i];
+                let video_id = request_description.video_id;
+                let latency = state.latency(endpoint_id, video_id);
+                if latency > cache_latency && !state.cached_videos[cache_id].contains(video_id) {
+                    *values.entry(video_id).or_insert(0) +=
+                        (latency - cache_latency) as u64 * request_description.weighted_amount();
+                }
+            }
+        }
+
+        let items: Vec<(Id, usize, u64)> = values
+            .into_iter()
+            .map(|(video_id, value)| {
+                let weight = input.videos[video_id].size.div_ceil(scale) as usize;
+                (video_id, weight, value)
+            })
+            .filter(|&(_, weight, _)| weight <= buckets)
+            .collect();
+
+        // best[w] is the highest value reachable within w buckets, taken[i][w] whether item i is
+        // part of that selection when only the first i + 1 items are considered
+        let mut best = vec![0u64; buckets + 1];
+        let mut taken = vec![vec![false; buckets + 1]; items.len()];
+        for (i, &(_, weight, value)) in items.iter().enumerate() {
+            for w in (weight..buckets + 1).rev() {
+                if best[w - weight] + value > best[w] {
+                    best[w] = best[w - weight] + value;
+                    taken[i][w] = true;
+                }
+            }
+        }
+
+        let mut w = buckets;
+        for (i, &(video_id, weight, _)) in items.iter().enumerate().rev() {
+            if taken[i][w] {
+                // Only refused once the cache reaches a limit on its number of videos or the videos
+                // placed so far exhaust the storage budget
+                if state.try_insert_video_in_cache(cache_id, video_id).is_ok() {
+                    pb.add(input.bytes(input.videos[video_id].size as u64));
+                }
+                w -= weight;
+            }
+        }
+    }
+}
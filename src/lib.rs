@@ -0,0 +1,2408 @@
+extern crate ctrlc;
+extern crate flate2;
+extern crate pbr;
+extern crate rand;
+extern crate rayon;
+
+use std::time::{Duration, Instant};
+use std::path::{Path, PathBuf};
+use std::io::Write;
+use std::io::sink;
+use std::io::{BufReader, BufWriter};
+use std::io::BufRead;
+use std::fmt;
+use std::fs::File;
+use std::io;
+use std::mem;
+use std::process;
+use std::str::{FromStr, SplitWhitespace};
+use std::collections::HashSet;
+use std::collections::HashMap;
+use std::collections::BinaryHeap;
+use std::cmp::Reverse;
+use std::convert::TryFrom;
+use std::sync::atomic::{AtomicBool, Ordering};
+use rayon::prelude::*;
+use pbr::{ProgressBar, Units};
+use flate2::read::GzDecoder;
+use rand::{Rng, SeedableRng, StdRng};
+
+mod beam;
+pub mod cli;
+mod cached;
+mod cluster;
+mod coverage;
+mod dot;
+mod endpoint_greedy;
+mod exact;
+mod generate;
+mod genetic;
+mod improve;
+mod knapsack;
+mod profile;
+mod repl;
+mod reserve;
+mod scores;
+mod solution;
+mod sorted;
+mod video_centric;
+
+use beam::beam_search;
+use cached::CachedVideos;
+use cluster::cluster_aware_greedy;
+use coverage::coverage_greedy;
+use endpoint_greedy::{endpoint_greedy, shuffled_endpoint_greedy};
+use exact::solve_exact;
+use genetic::genetic;
+use knapsack::knapsack;
+use profile::{CANDIDATE_SCANS, IS_CACHING_CALLS, PLACEMENTS, REQUEST_DESCRIPTIONS, SCORE_CALLS};
+use reserve::reserved_greedy;
+use scores::{load_scores, save_scores, ScoresKey};
+use sorted::sorted_greedy;
+use video_centric::video_centric_greedy;
+
+pub use cached::StateRepr;
+pub use cluster::cache_clusters;
+
+pub type Id = usize;
+
+// Set on Ctrl-C. Solvers stop placing videos once it's set so the current, still feasible, state
+// can be printed.
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug)]
+pub struct Video {
+    // In the input's size unit, see `Input::size_unit`
+    pub size: u32,
+}
+
+#[derive(Debug)]
+pub struct Endpoint {
+    pub latency: u32,
+    // Sorted by latency, lowest first
+    pub cache_connections: Vec<(Id, u32)>,
+}
+
+impl Endpoint {
+    // The cache connections with a lower latency than the datacenter's. Requests are never served
+    // through the others, as the datacenter is at least as fast.
+    fn faster_connections(&self) -> &[(Id, u32)] {
+        let faster = self
+            .cache_connections
+            .partition_point(|&(_, latency)| latency < self.latency);
+        &self.cache_connections[..faster]
+    }
+}
+
+#[derive(Debug)]
+pub struct Cache {}
+
+#[derive(Debug, Clone)]
+pub struct RequestDescription {
+    pub amount: u32,
+    pub video_id: Id,
+    pub endpoint_id: Id,
+    // How much the latency of these requests counts for, 1 unless the input gives a weight
+    pub weight: f64,
+}
+
+impl RequestDescription {
+    // The amount scaled by the weight, rounded to the nearest request. The latency saved for these
+    // requests counts as if this many had been made, both in placement and in scoring.
+    fn weighted_amount(&self) -> u64 {
+        if self.weight == 1.0 {
+            self.amount as u64
+        } else {
+            (self.amount as f64 * self.weight).round() as u64
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Input {
+    videos: Vec<Video>,
+    endpoints: Vec<Endpoint>,
+    caches: Vec<Cache>,
+    cache_size: u32,
+    request_descriptions: Vec<RequestDescription>,
+    // Indices into `request_descriptions` of the requests for each video
+    requests_by_video: Vec<Vec<usize>>,
+    // Indices into `request_descriptions` of the requests coming from each endpoint
+    requests_by_endpoint: Vec<Vec<usize>>,
+    // The endpoints connected to each cache
+    endpoints_by_cache: Vec<Vec<Id>>,
+    // How many units of video and cache size make up a megabyte: 1 unless the input gives sizes
+    // with a fractional part, in which case they're counted in `FRACTIONAL_UNIT`ths of a megabyte
+    // so that capacity checks stay exact
+    size_unit: u32,
+    // The numbers of videos and request descriptions the header declared, for `validate` to check.
+    // Inputs that weren't parsed declare what they hold.
+    declared_videos: usize,
+    declared_requests: usize,
+}
+
+impl Input {
+    // Videos larger than a cache, which can never be placed anywhere
+    fn uncacheable_videos(&self) -> HashSet<Id> {
+        self.videos
+            .iter()
+            .enumerate()
+            .filter(|&(_, video)| video.size > self.cache_size)
+            .map(|(video_id, _)| video_id)
+            .collect()
+    }
+
+    // Roughly the bytes allocated for each part of the input, from the capacity of its vectors
+    fn heap_sizes(&self) -> [(&'static str, usize); 4] {
+        let connections: usize = self
+            .endpoints
+            .iter()
+            .map(|endpoint| vec_size(&endpoint.cache_connections))
+            .sum();
+        let indices: usize = [
+            &self.requests_by_video,
+            &self.requests_by_endpoint,
+            &self.endpoints_by_cache,
+        ]
+        .iter()
+        .map(|index| vec_size(index) + index.iter().map(vec_size).sum::<usize>())
+        .sum();
+        [
+            ("videos", vec_size(&self.videos) + vec_size(&self.caches)),
+            ("endpoints", vec_size(&self.endpoints) + connections),
+            ("request descriptions", vec_size(&self.request_descriptions)),
+            ("indices", indices),
+        ]
+    }
+
+    // Whether any placement could save latency: some video that fits in a cache is requested from
+    // an endpoint with a cache faster than the datacenter. Not so when e.g. every cache has a size of
+    // zero, and only zero-size videos can be placed.
+    fn can_save_anything(&self) -> bool {
+        let uncacheable = self.uncacheable_videos();
+        self.request_descriptions.iter().any(|request_description| {
+            let endpoint = &self.endpoints[request_description.endpoint_id];
+            !uncacheable.contains(&request_description.video_id)
+                && endpoint
+                    .cache_connections
+                    .first()
+                    .is_some_and(|&(_, latency)| latency < endpoint.latency)
+        })
+    }
+
+    // `size` units of video or cache size in bytes, as the progress bar counts them
+    fn bytes(&self, size: u64) -> u64 {
+        size * 1_048_576 / self.size_unit as u64
+    }
+
+    // `size` units of video or cache size in megabytes, for showing to the user. Whole numbers
+    // print without a fractional part.
+    fn megabytes(&self, size: u64) -> f64 {
+        size as f64 / self.size_unit as f64
+    }
+
+    // Endpoints with caches, none of them faster than the datacenter, so that no placement can save
+    // their requests anything
+    fn useless_endpoints(&self) -> Vec<Id> {
+        (0..self.endpoints.len())
+            .filter(|&endpoint_id| {
+                let endpoint = &self.endpoints[endpoint_id];
+                !endpoint.cache_connections.is_empty() && endpoint.faster_connections().is_empty()
+            })
+            .collect()
+    }
+
+    // Caches no endpoint is connected to, which can never serve a request
+    pub fn unreachable_caches(&self) -> Vec<Id> {
+        (0..self.caches.len())
+            .filter(|&cache_id| self.endpoints_by_cache[cache_id].is_empty())
+            .collect()
+    }
+
+    pub fn new(
+        videos: Vec<Video>,
+        endpoints: Vec<Endpoint>,
+        caches: Vec<Cache>,
+        cache_size: u32,
+        mut request_descriptions: Vec<RequestDescription>,
+    ) -> Input {
+        // A fixed order keeps solvers that break ties by position deterministic across runs
+        request_descriptions.sort_by_key(|r| (r.endpoint_id, r.video_id));
+
+        let mut requests_by_video = vec![Vec::new(); videos.len()];
+        let mut requests_by_endpoint = vec![Vec::new(); endpoints.len()];
+        let mut endpoints_by_cache = vec![Vec::new(); caches.len()];
+
+        // Out of range ids, which parsing rejects, are left out of the indices
+        for (i, request_description) in request_descriptions.iter().enumerate() {
+            if let Some(requests) = requests_by_video.get_mut(request_description.video_id) {
+                requests.push(i);
+            }
+            if let Some(requests) = requests_by_endpoint.get_mut(request_description.endpoint_id) {
+                requests.push(i);
+            }
+        }
+
+        for (endpoint_id, endpoint) in endpoints.iter().enumerate() {
+            for &(cache_id, _) in endpoint.cache_connections.iter() {
+                if let Some(endpoint_ids) = endpoints_by_cache.get_mut(cache_id) {
+                    endpoint_ids.push(endpoint_id);
+                }
+            }
+        }
+
+        Input {
+            size_unit: 1,
+            declared_videos: videos.len(),
+            declared_requests: request_descriptions.len(),
+            videos,
+            endpoints,
+            caches,
+            cache_size,
+            request_descriptions,
+            requests_by_video,
+            requests_by_endpoint,
+            endpoints_by_cache,
+        }
+    }
+
+    // Checks that the input holds as many videos, endpoints and caches as its header declared, and
+    // that every cache connection refers to a cache that exists. Request descriptions are checked
+    // as they're parsed, where their line is known. Once they have been read, `requests_read` is
+    // the number of lines they took, which is checked too: duplicates are merged, so the input may
+    // hold fewer.
+    fn validate(&self, declared: Counts, requests_read: Option<usize>) -> Result<(), ParseError> {
+        let mut counts = vec![
+            ("videos", self.declared_videos, self.videos.len()),
+            ("endpoints", declared.endpoints, self.endpoints.len()),
+            ("caches", declared.caches, self.caches.len()),
+        ];
+        if let Some(requests_read) = requests_read {
+            counts.push(("request descriptions", self.declared_requests, requests_read));
+        }
+        for &(what, declared, actual) in counts.iter() {
+            if declared != actual {
+                return Err(ParseError::CountMismatch {
+                    what,
+                    declared,
+                    actual,
+                });
+            }
+        }
+
+        for (endpoint_id, endpoint) in self.endpoints.iter().enumerate() {
+            for &(cache_id, _) in endpoint.cache_connections.iter() {
+                if cache_id >= self.caches.len() {
+                    return Err(ParseError::UnknownCache {
+                        endpoint_id,
+                        cache_id,
+                    });
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// The bytes allocated for the elements of `v`, used or not
+fn vec_size<T>(v: &Vec<T>) -> usize {
+    v.capacity() * mem::size_of::<T>()
+}
+
+// The number of endpoints and caches an input's header declares, which are only needed while
+// parsing. The declared videos and request descriptions are kept on `Input`.
+#[derive(Debug, Clone, Copy)]
+struct Counts {
+    endpoints: usize,
+    caches: usize,
+}
+
+#[derive(Debug)]
+enum ParseError {
+    // The input file itself couldn't be opened
+    Open { path: PathBuf, err: io::Error },
+    Io(io::Error),
+    // A line is missing, or doesn't hold the fields the format expects there
+    Malformed { line: usize },
+    // The header declared a different number of videos, endpoints or caches than were read
+    CountMismatch {
+        what: &'static str,
+        declared: usize,
+        actual: usize,
+    },
+    UnknownCache { endpoint_id: Id, cache_id: Id },
+    // A request description on `line` refers to a video or endpoint beyond those the header declared
+    RequestOutOfRange {
+        line: usize,
+        video_id: Id,
+        endpoint_id: Id,
+    },
+}
+
+impl From<io::Error> for ParseError {
+    fn from(err: io::Error) -> ParseError {
+        ParseError::Io(err)
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ParseError::Open { ref path, ref err } => {
+                // Without the "(os error 2)" io::Error appends
+                let err = err.to_string();
+                let reason = err.find(" (os error").map_or(&err[..], |end| &err[..end]);
+                write!(f, "cannot open '{}': {}", path.display(), reason)
+            }
+            ParseError::Io(ref err) => write!(f, "{}", err),
+            ParseError::Malformed { line } => write!(f, "line {}: malformed", line),
+            ParseError::CountMismatch {
+                what,
+                declared,
+                actual,
+            } => write!(f, "{} {} declared but {} found", declared, what, actual),
+            ParseError::UnknownCache {
+                endpoint_id,
+                cache_id,
+            } => write!(
+                f,
+                "endpoint {} is connected to unknown cache {}",
+                endpoint_id, cache_id
+            ),
+            ParseError::RequestOutOfRange {
+                line,
+                video_id,
+                endpoint_id,
+            } => write!(
+                f,
+                "line {}: request for video {} from endpoint {} is out of range",
+                line, video_id, endpoint_id
+            ),
+        }
+    }
+}
+
+// `err` from parsing `path` as shown to the user, naming the file unless the error already does
+fn parse_failure<P: AsRef<Path>>(path: P, err: &ParseError) -> String {
+    match *err {
+        ParseError::Open { .. } => err.to_string(),
+        _ => format!("{}: {}", path.as_ref().display(), err),
+    }
+}
+
+// Whether `line` is a comment, starting with `#`, which inputs may contain anywhere
+fn is_comment(line: &str) -> bool {
+    line.trim_start().starts_with('#')
+}
+
+// Whether `line` carries nothing to parse, being blank or a comment
+fn is_ignored(line: &str) -> bool {
+    line.trim().is_empty() || is_comment(line)
+}
+
+// Reads an input line by line, keeping track of the line number for error messages
+struct LineReader {
+    reader: Box<dyn BufRead>,
+    number: usize,
+}
+
+impl LineReader {
+    // The next line without its line ending, or `None` at the end of the input. Blank lines and
+    // comments are skipped.
+    fn next_line(&mut self) -> Result<Option<String>, ParseError> {
+        self.read_line(is_ignored)
+    }
+
+    // The next line that `skip` doesn't reject
+    fn read_line<F>(&mut self, skip: F) -> Result<Option<String>, ParseError>
+    where
+        F: Fn(&str) -> bool,
+    {
+        loop {
+            let mut line = String::new();
+            if self.reader.read_line(&mut line)? == 0 {
+                return Ok(None);
+            }
+            self.number += 1;
+            if line.ends_with('\n') {
+                line.pop();
+                if line.ends_with('\r') {
+                    line.pop();
+                }
+            }
+            if !skip(&line) {
+                return Ok(Some(line));
+            }
+        }
+    }
+
+    // Everything that hasn't been read yet
+    fn rest(&mut self) -> Result<String, ParseError> {
+        let mut rest = String::new();
+        self.reader.read_to_string(&mut rest)?;
+        Ok(rest)
+    }
+
+    // The next line, which the format requires to be there
+    fn expect_line(&mut self) -> Result<String, ParseError> {
+        let line = self.next_line()?;
+        self.required(line)
+    }
+
+    // Like `expect_line` but only skips comments, for a line that is legitimately blank when the
+    // list it holds is empty
+    fn expect_list_line(&mut self) -> Result<String, ParseError> {
+        let line = self.read_line(is_comment)?;
+        self.required(line)
+    }
+
+    fn required(&self, line: Option<String>) -> Result<String, ParseError> {
+        line.ok_or(ParseError::Malformed {
+            line: self.number + 1,
+        })
+    }
+}
+
+// Parses the next whitespace separated field on line `line`
+fn field<T: FromStr>(parts: &mut SplitWhitespace, line: usize) -> Result<T, ParseError> {
+    parts
+        .next()
+        .and_then(|part| part.parse().ok())
+        .ok_or(ParseError::Malformed { line })
+}
+
+// Sizes with a fractional part are counted in thousandths of a megabyte, which keeps any size
+// given with up to three decimals exact
+const FRACTIONAL_UNIT: u32 = 1000;
+
+// Parses a video or cache size in megabytes, such as `50` or `1.5`, into `FRACTIONAL_UNIT`ths of a
+// megabyte. Sizes more precise than that are rejected rather than rounded.
+fn parse_size(size: &str) -> Option<u64> {
+    let (whole, fraction) = match size.find('.') {
+        Some(point) => (&size[..point], &size[point + 1..]),
+        None => (size, ""),
+    };
+    if whole.is_empty() || fraction.len() > 3 || !fraction.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let whole: u64 = whole.parse().ok()?;
+    let fraction: u64 = format!("{:0<3}", fraction).parse().ok()?;
+    whole
+        .checked_mul(FRACTIONAL_UNIT as u64)?
+        .checked_add(fraction)
+}
+
+// Parses everything up to the request descriptions, returning an `Input` without any requests, the
+// counts its header declared and the reader positioned at the first request description
+fn parse_preamble<P>(filename: P, debug: bool) -> Result<(Input, Counts, LineReader), ParseError>
+where
+    P: AsRef<Path>,
+{
+    let path = filename.as_ref();
+    let file = File::open(path).map_err(|err| ParseError::Open {
+        path: path.to_path_buf(),
+        err,
+    })?;
+    let mut reader = BufReader::new(file);
+    // Gzipped inputs are recognized by their magic bytes and decompressed on the fly
+    let reader: Box<dyn BufRead> = if reader.fill_buf()?.starts_with(&[0x1f, 0x8b]) {
+        Box::new(BufReader::new(GzDecoder::new(reader)))
+    } else {
+        Box::new(reader)
+    };
+    let mut lines = LineReader { reader, number: 0 };
+    let number_of_videos: usize;
+    let number_of_endpoints: usize;
+    let number_of_request_descriptions: usize;
+    let number_of_caches: usize;
+    let cache_size: u64;
+    let header_line: usize;
+    let sizes_line: usize;
+    let mut sizes = Vec::new();
+    let mut videos = Vec::new();
+    let mut endpoints = Vec::new();
+    let mut caches = Vec::new();
+
+    {
+        let line = lines.expect_line()?;
+        let mut parts = line.split_whitespace();
+        number_of_videos = field(&mut parts, lines.number)?;
+        number_of_endpoints = field(&mut parts, lines.number)?;
+        number_of_request_descriptions = field(&mut parts, lines.number)?;
+        number_of_caches = field(&mut parts, lines.number)?;
+        cache_size = parse_size(&field::<String>(&mut parts, lines.number)?)
+            .ok_or(ParseError::Malformed { line: lines.number })?;
+        header_line = lines.number;
+        if debug {
+            println!(
+                "{} videos, {} endpoints, {} request descriptions, {} caches {}MB each.",
+                number_of_videos,
+                number_of_endpoints,
+                number_of_request_descriptions,
+                number_of_caches,
+                cache_size
+            );
+        }
+    }
+
+    for _id in 0..number_of_caches {
+        caches.push(Cache {})
+    }
+
+    {
+        // With no videos the sizes line is blank, so blank lines can only be skipped before it when
+        // there are sizes to read
+        let line = if number_of_videos == 0 {
+            lines.expect_list_line()?
+        } else {
+            lines.expect_line()?
+        };
+        let parts = line.split_whitespace();
+        sizes_line = lines.number;
+        for (id, size) in parts.enumerate() {
+            if debug {
+                println!("Video #{}: {}MB", id, size);
+            }
+            sizes.push(parse_size(size).ok_or(ParseError::Malformed { line: lines.number })?);
+        }
+    }
+
+    // Sizes are read in `FRACTIONAL_UNIT`ths of a megabyte, and only kept that way if some size
+    // has a fractional part
+    let size_unit = if cache_size.is_multiple_of(FRACTIONAL_UNIT as u64)
+        && sizes
+            .iter()
+            .all(|size| size.is_multiple_of(FRACTIONAL_UNIT as u64))
+    {
+        1
+    } else {
+        FRACTIONAL_UNIT
+    };
+    let in_unit = |size: u64, line: usize| {
+        u32::try_from(size / (FRACTIONAL_UNIT / size_unit) as u64)
+            .map_err(|_| ParseError::Malformed { line })
+    };
+    let cache_size = in_unit(cache_size, header_line)?;
+    for size in sizes {
+        videos.push(Video {
+            size: in_unit(size, sizes_line)?,
+        });
+    }
+
+    {
+        for endpoint_id in 0..number_of_endpoints {
+            let line = lines.expect_line()?;
+            let latency: u32;
+            let number_of_caches: usize;
+            let mut cache_connections = Vec::new();
+
+            {
+                let mut parts = line.split_whitespace();
+                latency = field(&mut parts, lines.number)?;
+                number_of_caches = field(&mut parts, lines.number)?;
+                if debug {
+                    println!(
+                        "Endpoint {} has {}ms datacenter latency and is connected to {} \
+                         caches:",
+                        endpoint_id, latency, number_of_caches
+                    );
+                }
+            }
+
+            for _ in 0..number_of_caches {
+                let line = lines.expect_line()?;
+                let mut parts = line.split_whitespace();
+                let cache_id: usize = field(&mut parts, lines.number)?;
+                let cache_latency: u32 = field(&mut parts, lines.number)?;
+                cache_connections.push((cache_id, cache_latency));
+                if debug {
+                    println!{"The latency (of endpoint {}) to cache {} is {}ms.", endpoint_id, cache_id, cache_latency};
+                }
+            }
+
+            cache_connections.sort_by_key(|a| a.1);
+
+            endpoints.push(Endpoint {
+                latency,
+                cache_connections,
+            });
+        }
+    }
+
+    let counts = Counts {
+        endpoints: number_of_endpoints,
+        caches: number_of_caches,
+    };
+    let mut input = Input::new(videos, endpoints, caches, cache_size, Vec::new());
+    input.size_unit = size_unit;
+    input.declared_videos = number_of_videos;
+    input.declared_requests = number_of_request_descriptions;
+    input.validate(counts, None)?;
+
+    Ok((input, counts, lines))
+}
+
+// The next request description line, or `None` at the end of the input
+fn next_request(
+    lines: &mut LineReader,
+    preamble: &Input,
+    debug: bool,
+    decay: f64,
+) -> Result<Option<RequestDescription>, ParseError> {
+    match lines.next_line()? {
+        Some(line) => parse_request(&line, lines.number, preamble, debug, decay).map(Some),
+        None => Ok(None),
+    }
+}
+
+// Parses request description line `number`. A line may carry the weight of its requests as a fourth
+// field, which must not be negative. With a non-zero `decay` the fourth field is instead the age of
+// the requests, scaling the amount by `e^(-decay * age)` rounded to the nearest request, and the
+// weight may follow it. Lines without an age are taken as they are. The video and endpoint must be
+// among those of `preamble`.
+fn parse_request(
+    line: &str,
+    number: usize,
+    preamble: &Input,
+    debug: bool,
+    decay: f64,
+) -> Result<RequestDescription, ParseError> {
+    let mut parts = line.split_whitespace();
+    let video_id: usize = field(&mut parts, number)?;
+    let endpoint_id: usize = field(&mut parts, number)?;
+    parse_requests_for(parts, number, video_id, endpoint_id, preamble, debug, decay)
+}
+
+// Parses the fields of request description line `number` that follow its video and endpoint: the
+// amount, then the optional age and weight as `parse_request` describes. Anything after them is
+// malformed, so that ages aren't silently read as weights when `decay` is left out.
+fn parse_requests_for(
+    mut parts: SplitWhitespace,
+    number: usize,
+    video_id: Id,
+    endpoint_id: Id,
+    preamble: &Input,
+    debug: bool,
+    decay: f64,
+) -> Result<RequestDescription, ParseError> {
+    let mut amount: u32 = field(&mut parts, number)?;
+    if video_id >= preamble.videos.len() || endpoint_id >= preamble.endpoints.len() {
+        return Err(ParseError::RequestOutOfRange {
+            line: number,
+            video_id,
+            endpoint_id,
+        });
+    }
+    let mut weight: f64 = 1.0;
+    if decay != 0.0 && parts.clone().next().is_some() {
+        let age: u32 = field(&mut parts, number)?;
+        amount = (amount as f64 * (-decay * age as f64).exp()).round() as u32;
+    }
+    if parts.clone().next().is_some() {
+        weight = field(&mut parts, number)?;
+        if !(weight >= 0.0 && weight.is_finite()) {
+            return Err(ParseError::Malformed { line: number });
+        }
+    }
+    if parts.next().is_some() {
+        return Err(ParseError::Malformed { line: number });
+    }
+    if debug {
+        println!(
+            "{} requests for video {} coming from endpoint {}.",
+            amount, video_id, endpoint_id
+        );
+    }
+
+    Ok(RequestDescription {
+        amount,
+        video_id,
+        endpoint_id,
+        weight,
+    })
+}
+
+// Request descriptions by (video, endpoint), merging duplicates
+type Requests = HashMap<(Id, Id), RequestDescription>;
+
+// Adds `request` to the requests for the same video from the same endpoint in `requests`. Their
+// weight is the average of both weights by amount.
+fn add_request(requests: &mut Requests, request: RequestDescription) {
+    let merged = requests
+        .entry((request.video_id, request.endpoint_id))
+        .or_insert(RequestDescription {
+            amount: 0,
+            ..request
+        });
+    let amount = merged.amount + request.amount;
+    if amount > 0 {
+        merged.weight = (merged.amount as f64 * merged.weight
+            + request.amount as f64 * request.weight)
+            / amount as f64;
+    }
+    merged.amount = amount;
+}
+
+// Adds the amounts of `b` to `a`, for merging the partial maps of a parallel parse
+fn merge_requests(mut a: Requests, mut b: Requests) -> Requests {
+    if a.len() < b.len() {
+        std::mem::swap(&mut a, &mut b);
+    }
+    for (_, request) in b {
+        add_request(&mut a, request);
+    }
+    a
+}
+
+// How the request descriptions of an input are laid out after the endpoints
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum InputFormat {
+    // One request description per line, `<video> <endpoint> <amount>`, as in the official inputs
+    Flat,
+    // Grouped by endpoint: an `<endpoint> <count>` line followed by `count` lines of
+    // `<video> <amount>`. The header counts these lines as the request descriptions.
+    Grouped,
+}
+
+impl FromStr for InputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<InputFormat, String> {
+        match s {
+            "flat" => Ok(InputFormat::Flat),
+            "grouped" => Ok(InputFormat::Grouped),
+            _ => Err(format!(
+                "unknown input format '{}', expected flat or grouped",
+                s
+            )),
+        }
+    }
+}
+
+// Reads the request descriptions of a grouped input, returning them merged and the number of lines
+// they took
+fn parse_grouped_requests(
+    lines: &mut LineReader,
+    preamble: &Input,
+    debug: bool,
+    decay: f64,
+) -> Result<(Requests, usize), ParseError> {
+    let mut request_descriptions = HashMap::new();
+    let mut requests_read = 0;
+    while let Some(line) = lines.next_line()? {
+        let mut parts = line.split_whitespace();
+        let endpoint_id: Id = field(&mut parts, lines.number)?;
+        let count: usize = field(&mut parts, lines.number)?;
+        if endpoint_id >= preamble.endpoints.len() || parts.next().is_some() {
+            return Err(ParseError::Malformed { line: lines.number });
+        }
+        for _ in 0..count {
+            let line = lines.expect_line()?;
+            let mut parts = line.split_whitespace();
+            let video_id: Id = field(&mut parts, lines.number)?;
+            let request = parse_requests_for(
+                parts,
+                lines.number,
+                video_id,
+                endpoint_id,
+                preamble,
+                debug,
+                decay,
+            )?;
+            add_request(&mut request_descriptions, request);
+        }
+        requests_read += count;
+    }
+    Ok((request_descriptions, requests_read))
+}
+
+// Parses a flat input, down-weighting requests by their age with `decay` as `next_request`
+// describes. A decay of zero reads the amounts as they are.
+fn parse_input<P>(filename: P, debug: bool, decay: f64) -> Result<Input, ParseError>
+where
+    P: AsRef<Path>,
+{
+    parse_input_timed(filename, InputFormat::Flat, debug, decay).map(|(input, _)| input)
+}
+
+// Like `parse_input` for an input in `format`, also returning how long building the indices took
+// out of the whole parse
+fn parse_input_timed<P>(
+    filename: P,
+    format: InputFormat,
+    debug: bool,
+    decay: f64,
+) -> Result<(Input, Duration), ParseError>
+where
+    P: AsRef<Path>,
+{
+    let (preamble, counts, mut lines) = parse_preamble(filename, debug)?;
+    let (request_descriptions, requests_read) = match format {
+        InputFormat::Flat => parse_flat_requests(&mut lines, &preamble, debug, decay)?,
+        InputFormat::Grouped => parse_grouped_requests(&mut lines, &preamble, debug, decay)?,
+    };
+
+    let indexing = Instant::now();
+    let mut input = Input::new(
+        preamble.videos,
+        preamble.endpoints,
+        preamble.caches,
+        preamble.cache_size,
+        request_descriptions.into_values().collect(),
+    );
+    let indexing = indexing.elapsed();
+    input.size_unit = preamble.size_unit;
+    input.declared_videos = preamble.declared_videos;
+    input.declared_requests = preamble.declared_requests;
+    input.validate(counts, Some(requests_read))?;
+
+    Ok((input, indexing))
+}
+
+// Reads the request descriptions of a flat input, returning them merged and the number of lines
+// they took
+fn parse_flat_requests(
+    lines: &mut LineReader,
+    preamble: &Input,
+    debug: bool,
+    decay: f64,
+) -> Result<(Requests, usize), ParseError> {
+    // The request descriptions don't depend on each other, so they're read in one go and parsed in
+    // parallel. Duplicates of request descriptions that are requesting the same video from the same
+    // endpoint are merged into partial maps as they're parsed. Partial results are combined in
+    // order, so a malformed input always reports its first bad line. With `debug` the request
+    // descriptions are printed in no particular order.
+    let rest = lines.rest()?;
+    let rest: Vec<&str> = rest.lines().collect();
+    let first = lines.number + 1;
+    let requests_read = rest.iter().filter(|line| !is_ignored(line)).count();
+    let request_descriptions: Requests = rest
+        .par_iter()
+        .enumerate()
+        .filter(|&(_, line)| !is_ignored(line))
+        .fold(
+            || Ok(HashMap::new()),
+            |request_descriptions: Result<Requests, ParseError>, (i, line)| {
+                let mut request_descriptions = request_descriptions?;
+                let request = parse_request(line, first + i, preamble, debug, decay)?;
+                add_request(&mut request_descriptions, request);
+                Ok(request_descriptions)
+            },
+        )
+        .reduce(
+            || Ok(HashMap::new()),
+            |a, b| Ok(merge_requests(a?, b?)),
+        )?;
+    Ok((request_descriptions, requests_read))
+}
+
+// Scores `state` against the request descriptions in `filename` as they're read, without ever
+// holding them in memory. `state` must be built on the input `parse_preamble` returns for the same
+// file. Only the videos, endpoints and the state itself stay resident, so for the largest inputs
+// this needs a fraction of what `parse_input` does: no request list, no per-video and per-endpoint
+// indices and no deduplication map. Verifying a kittens solution peaks at 27MB this way against
+// 46MB. Duplicate request descriptions need no merging since each
+// request adds to the score independently.
+fn parse_and_score_stream<P>(filename: P, state: &State) -> Result<Score, ParseError>
+where
+    P: AsRef<Path>,
+{
+    let (preamble, _, mut lines) = parse_preamble(filename, false)?;
+    let mut sum_latency: u128 = 0;
+    let mut sum_requests = 0;
+
+    while let Some(request_description) = next_request(&mut lines, &preamble, false, 0.0)? {
+        let endpoint = &state.input.endpoints[request_description.endpoint_id];
+        let latency = state.latency(request_description.endpoint_id, request_description.video_id);
+        sum_latency += (endpoint.latency - latency) as u128
+            * request_description.weighted_amount() as u128;
+        sum_requests += request_description.amount as u64;
+    }
+
+    Ok(Score::new(sum_latency, sum_requests))
+}
+
+// Parses flat `filename` with `decay`, exiting with an error message if it can't be read
+fn load_input<P>(filename: P, decay: f64) -> Input
+where
+    P: AsRef<Path>,
+{
+    load_input_timed(filename, InputFormat::Flat, decay).0
+}
+
+// Like `load_input` for an input in `format`, also returning how long building the indices took
+fn load_input_timed<P>(filename: P, format: InputFormat, decay: f64) -> (Input, Duration)
+where
+    P: AsRef<Path>,
+{
+    parse_input_timed(&filename, format, false, decay).unwrap_or_else(|err| {
+        eprintln!("error: {}", parse_failure(&filename, &err));
+        process::exit(1);
+    })
+}
+
+// The number of requests across every request description of the input
+fn request_volume(input: &Input) -> u64 {
+    input
+        .request_descriptions
+        .iter()
+        .map(|request_description| request_description.amount as u64)
+        .sum()
+}
+
+// The score of the empty solution, serving every request from the datacenter
+fn baseline_score(input: &Input) -> Score {
+    Score::new(0, request_volume(input))
+}
+
+// A floor any strategy should beat: each cache holds just the video that fits in it and is requested
+// most often through it, the lowest id among equals
+fn naive_baseline(input: &Input) -> State<'_> {
+    let mut state = State::new(input);
+    for cache_id in 0..input.caches.len() {
+        let mut demand: HashMap<Id, u64> = HashMap::new();
+        for &endpoint_id in input.endpoints_by_cache[cache_id].iter() {
+            for &i in input.requests_by_endpoint[endpoint_id].iter() {
+                let request_description = &input.request_descriptions[i];
+                if input.videos[request_description.video_id].size <= input.cache_size {
+                    *demand.entry(request_description.video_id).or_insert(0) +=
+                        request_description.weighted_amount();
+                }
+            }
+        }
+        if let Some((video_id, _)) = demand
+            .into_iter()
+            .max_by_key(|&(video_id, amount)| (amount, Reverse(video_id)))
+        {
+            state.insert_video_in_cache(cache_id, video_id);
+        }
+    }
+    state
+}
+
+// The total latency saved if every cache could hold every video, i.e. each request is served by
+// the lowest-latency cache connected to its endpoint. No solution can score higher than this.
+fn upper_bound(input: &Input) -> u128 {
+    input
+        .request_descriptions
+        .iter()
+        .map(|request_description| {
+            let endpoint = &input.endpoints[request_description.endpoint_id];
+            endpoint
+                .cache_connections
+                .first()
+                .map_or(0, |&(_, cache_latency)| {
+                    endpoint.latency.saturating_sub(cache_latency) as u128
+                        * request_description.weighted_amount() as u128
+                })
+        })
+        .sum()
+}
+
+// The share of `upper_bound` that `score` saves, from 0 to 1, which unlike the score itself can be
+// compared across inputs. Zero when nothing can be saved at all.
+fn normalized_score(input: &Input, score: &Score) -> f64 {
+    let upper_bound = upper_bound(input);
+    if upper_bound == 0 {
+        0.0
+    } else {
+        score.total_saved as f64 / upper_bound as f64
+    }
+}
+
+// The LP relaxation of each cache on its own: the videos requested through it ranked by latency
+// saved per megabyte, filled up to its capacity with a fraction of the first video that doesn't fit.
+// Summed over the caches this can't be beaten either, but it counts an endpoint connected to several
+// caches once per cache, so it's capped by `upper_bound`. It's tighter when caches are small
+// relative to the videos and rarely share endpoints.
+fn fractional_bound(input: &Input) -> f64 {
+    let relaxed: f64 = (0..input.caches.len())
+        .into_par_iter()
+        .map(|cache_id| {
+            let mut values: HashMap<Id, u64> = HashMap::new();
+            for &endpoint_id in input.endpoints_by_cache[cache_id].iter() {
+                let endpoint = &input.endpoints[endpoint_id];
+                let &(_, cache_latency) = endpoint
+                    .cache_connections
+                    .iter()
+                    .find(|&&(id, _)| id == cache_id)
+                    .unwrap();
+                for &i in input.requests_by_endpoint[endpoint_id].iter() {
+                    let request_description = &input.request_descriptions[i];
+                    *values.entry(request_description.video_id).or_insert(0) +=
+                        endpoint.latency.saturating_sub(cache_latency) as u64
+                            * request_description.weighted_amount();
+                }
+            }
+
+            // Zero-size videos have an infinite density and are always taken whole. Videos that
+            // save nothing add nothing to the bound, and would be 0/0 if they take no space.
+            let mut values: Vec<(f64, u32, u64)> = values
+                .into_iter()
+                .filter(|&(_, value)| value > 0)
+                .map(|(video_id, value)| {
+                    let size = input.videos[video_id].size;
+                    (value as f64 / size as f64, size, value)
+                })
+                .collect();
+            values.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+            let mut capacity = input.cache_size as f64;
+            let mut bound = 0.0;
+            for (_, size, value) in values {
+                if size as f64 <= capacity {
+                    capacity -= size as f64;
+                    bound += value as f64;
+                } else {
+                    bound += value as f64 * capacity / size as f64;
+                    break;
+                }
+            }
+            bound
+        })
+        .sum();
+    relaxed.min(upper_bound(input) as f64)
+}
+
+#[derive(Debug, PartialEq)]
+struct CacheFull {
+    cache_id: Id,
+    video_id: Id,
+}
+
+impl fmt::Display for CacheFull {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "video {} doesn't fit in cache {}",
+            self.video_id, self.cache_id
+        )
+    }
+}
+
+// Ordered by the latency saved, then by the judge's score. For solutions of the same input the two
+// always agree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Score {
+    // Milliseconds saved across all requests compared to serving everything from the datacenter. A
+    // single request description can save up to (2^32)^2 and there can be any number of them, so
+    // this is a u128 and no input can make it wrap.
+    pub total_saved: u128,
+    // The score the judge reports: the average time saved per request in microseconds, rounded
+    // down. At most 1000 times the largest latency, which fits a u64.
+    pub per_request_avg_x1000: u64,
+}
+
+impl Score {
+    // The score of saving `sum_latency` milliseconds across `sum_requests` requests
+    fn new(sum_latency: u128, sum_requests: u64) -> Score {
+        if sum_requests == 0 {
+            return Score {
+                total_saved: 0,
+                per_request_avg_x1000: 0,
+            };
+        }
+
+        // The judge reports the average time saved per request in microseconds, rounded down.
+        // Integer division floors exactly where the floating point version could be off by one.
+        Score {
+            total_saved: sum_latency,
+            per_request_avg_x1000: (sum_latency * 1000 / sum_requests as u128) as u64,
+        }
+    }
+}
+
+impl fmt::Display for Score {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} ({}ms saved in total)",
+            self.per_request_avg_x1000, self.total_saved
+        )
+    }
+}
+
+#[derive(Debug, Default)]
+struct ScoreBreakdown {
+    sum_latency: u128,
+    // Total number of requests, i.e. the sum of every request description's amount
+    sum_requests: u64,
+    // Request descriptions served from some cache and from the datacenter respectively
+    served: usize,
+    unserved: usize,
+    // Number of requests served from some cache
+    served_requests: u64,
+    // Endpoints with at least one request description served from some cache
+    served_endpoints: usize,
+}
+
+// The closest cache holding the video of `request_description` and the latency at which it serves
+// those requests, or None if they're served from the datacenter
+fn serving_cache<F>(
+    input: &Input,
+    request_description: &RequestDescription,
+    is_cached: F,
+) -> Option<(Id, u32)>
+where
+    F: Fn(Id, Id) -> bool,
+{
+    // Endpoints without caches faster than the datacenter are always served from the datacenter
+    input.endpoints[request_description.endpoint_id]
+        .faster_connections()
+        .iter()
+        .find(|&&(cache_id, _)| is_cached(cache_id, request_description.video_id))
+        .cloned()
+}
+
+// Tallies the latency saved on `request_descriptions` by the placement in which
+// `is_cached(cache_id, video_id)` tells whether a cache holds a video, along with how much of their
+// demand is served from a cache at all
+fn tally<'b, I, F>(input: &Input, request_descriptions: I, is_cached: F) -> ScoreBreakdown
+where
+    I: IntoIterator<Item = &'b RequestDescription>,
+    F: Fn(Id, Id) -> bool,
+{
+    let mut breakdown = ScoreBreakdown::default();
+    let mut served_endpoints = HashSet::new();
+
+    for request_description in request_descriptions {
+        profile::count(&REQUEST_DESCRIPTIONS, 1);
+        let endpoint = &input.endpoints[request_description.endpoint_id];
+
+        breakdown.sum_requests += request_description.amount as u64;
+
+        if let Some((_, latency)) = serving_cache(input, request_description, &is_cached) {
+            breakdown.sum_latency += (endpoint.latency - latency) as u128
+                * request_description.weighted_amount() as u128;
+            breakdown.served += 1;
+            breakdown.served_requests += request_description.amount as u64;
+            served_endpoints.insert(request_description.endpoint_id);
+        } else {
+            breakdown.unserved += 1;
+        }
+    }
+
+    breakdown.served_endpoints = served_endpoints.len();
+    breakdown
+}
+
+// Scores a per-cache assignment of videos built outside of a `State`, e.g. by another tool. Caches
+// beyond the end of `placement` hold nothing.
+pub fn score_placement(input: &Input, placement: &[HashSet<Id>]) -> Score {
+    let breakdown = tally(input, &input.request_descriptions, |cache_id, video_id| {
+        placement
+            .get(cache_id)
+            .is_some_and(|videos| videos.contains(&video_id))
+    });
+    Score::new(breakdown.sum_latency, breakdown.sum_requests)
+}
+
+// A placement of videos in the caches of `input`, holding each cache's videos in the container
+// chosen with `StateRepr`
+#[derive(Debug, Clone)]
+pub struct State<'a> {
+    cached_videos: Vec<CachedVideos>,
+    // Kept as u64 so that even an infeasible assignment, e.g. one read from a solution file, can't
+    // overflow
+    cache_usage: Vec<u64>,
+    // Set by `--max-videos-per-cache` to cap how many videos any cache may hold
+    max_videos_per_cache: Option<usize>,
+    // Set by `--budget` to cap the size of all videos placed across the caches, in the input's size
+    // unit, and the size placed so far, i.e. the sum of `cache_usage`
+    budget: Option<u64>,
+    placed: u64,
+    input: &'a Input,
+}
+
+impl<'a> State<'a> {
+    pub fn new(input: &Input) -> State<'_> {
+        State::with_repr(input, StateRepr::HashSet)
+    }
+
+    // An empty state whose caches hold their videos in `repr`
+    pub fn with_repr(input: &Input, repr: StateRepr) -> State<'_> {
+        State {
+            cached_videos: vec![CachedVideos::new(repr, input.videos.len()); input.caches.len()],
+            cache_usage: vec![0; input.caches.len()],
+            max_videos_per_cache: None,
+            budget: None,
+            placed: 0,
+            input,
+        }
+    }
+
+    // Roughly the bytes allocated for the placement, not counting the input
+    fn heap_size(&self) -> usize {
+        vec_size(&self.cached_videos)
+            + self
+                .cached_videos
+                .iter()
+                .map(|videos| videos.heap_size())
+                .sum::<usize>()
+            + vec_size(&self.cache_usage)
+    }
+
+    fn cache_usage(&self, cache_id: Id) -> u64 {
+        self.cache_usage[cache_id]
+    }
+
+    fn remaining_capacity(&self, cache_id: Id) -> u64 {
+        (self.input.cache_size as u64).saturating_sub(self.cache_usage(cache_id))
+    }
+
+    // Whether `video_id` fits in the space `cache_id` has left and within any budget, and the cache
+    // is below any limit on the number of videos
+    fn fits(&self, cache_id: Id, video_id: Id) -> bool {
+        let size = self.input.videos[video_id].size as u64;
+        self.max_videos_per_cache
+            .is_none_or(|max| self.cached_videos[cache_id].len() < max)
+            && size <= self.remaining_capacity(cache_id)
+            && self.budget.is_none_or(|budget| self.placed + size <= budget)
+    }
+
+    // The latency at which `endpoint_id` currently receives `video_id`
+    fn latency(&self, endpoint_id: Id, video_id: Id) -> u32 {
+        let endpoint = &self.input.endpoints[endpoint_id];
+        endpoint
+            .faster_connections()
+            .iter()
+            .find(|&&(cache_id, _)| self.cached_videos[cache_id].contains(video_id))
+            .map_or(endpoint.latency, |&(_, latency)| latency)
+    }
+
+    // The latency at which `endpoint_id` currently receives each video it requests, in the order of
+    // its request descriptions
+    pub fn endpoint_effective_latency(&self, endpoint_id: Id) -> Vec<(Id, u32)> {
+        self.input.requests_by_endpoint[endpoint_id]
+            .iter()
+            .map(|&i| {
+                let video_id = self.input.request_descriptions[i].video_id;
+                (video_id, self.latency(endpoint_id, video_id))
+            })
+            .collect()
+    }
+
+    // The latency saved across all requests for `video_id`
+    fn video_saving(&self, video_id: Id) -> u64 {
+        self.input.requests_by_video[video_id]
+            .iter()
+            .map(|&i| {
+                let request_description = &self.input.request_descriptions[i];
+                let endpoint = &self.input.endpoints[request_description.endpoint_id];
+                let latency = self.latency(request_description.endpoint_id, video_id);
+                (endpoint.latency - latency) as u64 * request_description.weighted_amount()
+            })
+            .sum()
+    }
+
+    // The caches with room left for `video_id` that don't hold it yet
+    pub fn eligible_caches(&self, video_id: Id) -> Vec<Id> {
+        (0..self.input.caches.len())
+            .filter(|&cache_id| {
+                self.fits(cache_id, video_id) && !self.cached_videos[cache_id].contains(video_id)
+            })
+            .collect()
+    }
+
+    // The lowest latency cache connected to `endpoint_id` that could still take `video_id` and would
+    // serve it faster than the datacenter
+    pub fn best_cache_for(&self, endpoint_id: Id, video_id: Id) -> Option<Id> {
+        self.input.endpoints[endpoint_id]
+            .faster_connections()
+            .iter()
+            .map(|&(cache_id, _)| cache_id)
+            .find(|&cache_id| {
+                self.fits(cache_id, video_id) && !self.cached_videos[cache_id].contains(video_id)
+            })
+    }
+
+    fn is_caching(&self, endpoint_id: Id, video_id: Id) -> bool {
+        profile::count(&IS_CACHING_CALLS, 1);
+        self.input.endpoints[endpoint_id]
+            .faster_connections()
+            .iter()
+            .any(|&(cache_id, _)| self.cached_videos[cache_id].contains(video_id))
+    }
+
+    // Whether every endpoint in `endpoint_ids` is already served `video_id` by another cache with
+    // an equal or lower latency than `cache_id`, making a placement in `cache_id` worthless.
+    fn is_redundant(&self, cache_id: Id, video_id: Id, endpoint_ids: &[Id]) -> bool {
+        endpoint_ids.iter().all(|&endpoint_id| {
+            let cache_connections = &self.input.endpoints[endpoint_id].cache_connections;
+            let latency = cache_connections
+                .iter()
+                .find(|&&(id, _)| id == cache_id)
+                .map(|&(_, latency)| latency)
+                .unwrap();
+
+            cache_connections
+                .iter()
+                .take_while(|&&(_, cache_latency)| cache_latency <= latency)
+                .any(|&(id, _)| id != cache_id && self.cached_videos[id].contains(video_id))
+        })
+    }
+
+    // Places `video_id` in `cache_id` whether or not it fits, see `try_insert_video_in_cache`
+    pub fn insert_video_in_cache(&mut self, cache_id: Id, video_id: Id) {
+        if self.cached_videos[cache_id].insert(video_id) {
+            profile::count(&PLACEMENTS, 1);
+            self.cache_usage[cache_id] += self.input.videos[video_id].size as u64;
+            self.placed += self.input.videos[video_id].size as u64;
+        }
+    }
+
+    // Like `insert_video_in_cache` but refuses, leaving the state untouched, if the video doesn't fit.
+    // Solvers should prefer this unless they've already checked the capacity in a hot loop.
+    fn try_insert_video_in_cache(&mut self, cache_id: Id, video_id: Id) -> Result<(), CacheFull> {
+        if !self.fits(cache_id, video_id) {
+            return Err(CacheFull { cache_id, video_id });
+        }
+        self.insert_video_in_cache(cache_id, video_id);
+        Ok(())
+    }
+
+    fn remove_video_from_cache(&mut self, cache_id: Id, video_id: Id) {
+        if self.cached_videos[cache_id].remove(video_id) {
+            self.cache_usage[cache_id] -= self.input.videos[video_id].size as u64;
+            self.placed -= self.input.videos[video_id].size as u64;
+        }
+    }
+
+    // Removes every video from `cache_id`, returning what it held
+    fn clear_cache(&mut self, cache_id: Id) -> Vec<Id> {
+        let videos: Vec<Id> = self.cached_videos[cache_id].iter().collect();
+        for &video_id in videos.iter() {
+            self.remove_video_from_cache(cache_id, video_id);
+        }
+        videos
+    }
+
+    // Tallies the latency saved along with how much of the demand is served from a cache at all
+    fn score_breakdown(&self) -> ScoreBreakdown {
+        tally(
+            self.input,
+            &self.input.request_descriptions,
+            |cache_id, video_id| self.cached_videos[cache_id].contains(video_id),
+        )
+    }
+
+    // The `n` request descriptions that save the most latency, with the milliseconds each saves,
+    // most first. Ties keep the input's order, and those served from the datacenter are left out,
+    // so all of them together save `score().total_saved`.
+    fn top_contributors(&self, n: usize) -> Vec<(RequestDescription, u64)> {
+        let mut contributions: Vec<(RequestDescription, u64)> = self
+            .input
+            .request_descriptions
+            .iter()
+            .filter_map(|request_description| {
+                let endpoint = &self.input.endpoints[request_description.endpoint_id];
+                serving_cache(self.input, request_description, |cache_id, video_id| {
+                    self.cached_videos[cache_id].contains(video_id)
+                })
+                .map(|(_, latency)| {
+                    let saved = (endpoint.latency - latency) as u64
+                        * request_description.weighted_amount();
+                    (request_description.clone(), saved)
+                })
+            })
+            .collect();
+        contributions.sort_by_key(|&(_, saved)| Reverse(saved));
+        contributions.truncate(n);
+        contributions
+    }
+
+    // Every placement with the endpoints it serves, in ascending order, and the latency it saves
+    // them, most first and then by cache and video. Requests are credited to the closest cache
+    // holding their video, so the savings add up to `score().total_saved`, and a placement whose
+    // endpoints all get the video faster elsewhere serves none and saves nothing.
+    fn placement_savings(&self) -> Vec<(Id, Id, Vec<Id>, u128)> {
+        let mut savings: HashMap<(Id, Id), (Vec<Id>, u128)> = HashMap::new();
+        for cache_id in 0..self.input.caches.len() {
+            for video_id in self.cached_videos[cache_id].iter() {
+                savings.insert((cache_id, video_id), (Vec::new(), 0));
+            }
+        }
+        for request_description in self.input.request_descriptions.iter() {
+            let serving = serving_cache(self.input, request_description, |cache_id, video_id| {
+                self.cached_videos[cache_id].contains(video_id)
+            });
+            if let Some((cache_id, latency)) = serving {
+                let endpoint = &self.input.endpoints[request_description.endpoint_id];
+                let placement = savings
+                    .get_mut(&(cache_id, request_description.video_id))
+                    .unwrap();
+                placement.0.push(request_description.endpoint_id);
+                placement.1 += (endpoint.latency - latency) as u128
+                    * request_description.weighted_amount() as u128;
+            }
+        }
+
+        let mut savings: Vec<(Id, Id, Vec<Id>, u128)> = savings
+            .into_iter()
+            .map(|((cache_id, video_id), (mut endpoint_ids, saved))| {
+                endpoint_ids.sort();
+                (cache_id, video_id, endpoint_ids, saved)
+            })
+            .collect();
+        savings.sort_by_key(|&(cache_id, video_id, _, saved)| (Reverse(saved), cache_id, video_id));
+        savings
+    }
+
+    // The number of distinct videos cached anywhere and the number of placements across all caches,
+    // which is larger by every extra copy of a video
+    fn redundancy(&self) -> (usize, usize) {
+        let distinct: HashSet<Id> = self
+            .cached_videos
+            .iter()
+            .flat_map(|videos| videos.iter())
+            .collect();
+        let slots = self.cached_videos.iter().map(|videos| videos.len()).sum();
+        (distinct.len(), slots)
+    }
+
+    // The score counting only the requests from `endpoint_ids`, which must be distinct. Scores of
+    // disjoint sets of endpoints add up to the score of their union, so a change confined to some
+    // endpoints can be measured on those alone.
+    fn score_subset(&self, endpoint_ids: &[Id]) -> Score {
+        let request_descriptions = endpoint_ids.iter().flat_map(|&endpoint_id| {
+            self.input.requests_by_endpoint[endpoint_id]
+                .iter()
+                .map(|&i| &self.input.request_descriptions[i])
+        });
+        let breakdown = tally(self.input, request_descriptions, |cache_id, video_id| {
+            self.cached_videos[cache_id].contains(video_id)
+        });
+        Score::new(breakdown.sum_latency, breakdown.sum_requests)
+    }
+
+    pub fn score(&self) -> Score {
+        profile::count(&SCORE_CALLS, 1);
+        let breakdown = self.score_breakdown();
+        Score::new(breakdown.sum_latency, breakdown.sum_requests)
+    }
+
+    // The solution in the submission format, written a line at a time so that it's never held in
+    // memory as a whole
+    fn write_output<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        self.write_caches(w, (0..self.input.caches.len()).collect())
+    }
+
+    // Like `write_output` but leaves out the caches without videos, for inspection rather than
+    // submission
+    fn write_output_nonempty<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        self.write_caches(
+            w,
+            (0..self.input.caches.len())
+                .filter(|&cache_id| !self.cached_videos[cache_id].is_empty())
+                .collect(),
+        )
+    }
+
+    // `write_output` into a string
+    pub fn output(&self) -> String {
+        let mut buffer = Vec::new();
+        self.write_output(&mut buffer).unwrap();
+        String::from_utf8(buffer).unwrap()
+    }
+
+    // A comment line per cache with its usage and number of videos, for reviewing a solution next
+    // to the submission
+    fn annotations(&self) -> String {
+        (0..self.input.caches.len())
+            .map(|cache_id| {
+                format!(
+                    "# cache {}: {}/{} MB, {} videos\n",
+                    cache_id,
+                    self.input.megabytes(self.cache_usage(cache_id)),
+                    self.input.megabytes(self.input.cache_size as u64),
+                    self.cached_videos[cache_id].len()
+                )
+            })
+            .collect()
+    }
+
+    // The submission format restricted to `cache_ids`
+    fn write_caches<W: Write>(&self, w: &mut W, cache_ids: Vec<Id>) -> io::Result<()> {
+        writeln!(w, "{}", cache_ids.len())?;
+
+        for cache_id in cache_ids {
+            // Sorted so that identical solutions print identically whatever the container
+            let mut videos: Vec<Id> = self.cached_videos[cache_id].iter().collect();
+            videos.sort();
+            write!(w, "{}", cache_id)?;
+
+            for video_id in videos {
+                write!(w, " {}", video_id)?;
+            }
+
+            writeln!(w)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn greedy_next(state: &State) -> Option<(u64, (Id, Id))> {
+    profile::count(&CANDIDATE_SCANS, 1);
+    profile::count(
+        &REQUEST_DESCRIPTIONS,
+        state.input.request_descriptions.len() as u64,
+    );
+    state
+        .input
+        .request_descriptions
+        .par_iter()
+        .filter_map(|request_description| {
+            let endpoint = &state.input.endpoints[request_description.endpoint_id];
+
+            if state.is_caching(
+                request_description.endpoint_id,
+                request_description.video_id,
+            ) {
+                return None;
+            }
+
+            state
+                .best_cache_for(request_description.endpoint_id, request_description.video_id)
+                .map(|cache_id| {
+                    let &(_, cache_latency) = endpoint
+                        .cache_connections
+                        .iter()
+                        .find(|&&(id, _)| id == cache_id)
+                        .unwrap();
+                    (
+                        (endpoint.latency - cache_latency) as u64
+                            * request_description.weighted_amount(),
+                        (request_description.video_id, cache_id),
+                    )
+                })
+        })
+        // Ties go to the lowest (video, cache) so the result doesn't depend on the thread count
+        .max_by_key(|&(gain, placement)| (gain, Reverse(placement)))
+}
+
+fn greedy<T: Write>(state: &mut State, pb: &mut ProgressBar<T>) {
+    while let Some((_, (video_id, cache_id))) = greedy_next(state) {
+        if INTERRUPTED.load(Ordering::Relaxed) {
+            return;
+        }
+        state.insert_video_in_cache(cache_id, video_id);
+        pb.add(state.input.bytes(state.input.videos[video_id].size as u64));
+    }
+}
+
+// For each cache, the accumulated score and contributing endpoints of every video it could hold
+type CacheCandidates = Vec<HashMap<Id, (u64, Vec<Id>)>>;
+
+// The endpoints that would benefit from each (cache, video) placement
+type CandidateEndpoints = HashMap<(Id, Id), Vec<Id>>;
+
+// The latency each cache would save per megabyte of every video requested through it, summed over
+// the request descriptions of its endpoints along with the endpoints that contributed. Caches are
+// independent of each other so they're built in parallel, and a solver working on one cache can
+// look up its candidates without scanning every request.
+fn cache_candidates(input: &Input) -> CacheCandidates {
+    // Requests for videos that fit in no cache can't be helped by any placement
+    let uncacheable = input.uncacheable_videos();
+    profile::count(&CANDIDATE_SCANS, 1);
+
+    (0..input.caches.len())
+        .into_par_iter()
+        .map(|cache_id| {
+            let mut candidates = HashMap::new();
+            for &endpoint_id in input.endpoints_by_cache[cache_id].iter() {
+                let endpoint = &input.endpoints[endpoint_id];
+                let &(_, cache_latency) = endpoint
+                    .cache_connections
+                    .iter()
+                    .find(|&&(id, _)| id == cache_id)
+                    .unwrap();
+                // Nothing requested from here can be served faster through this cache
+                if cache_latency >= endpoint.latency {
+                    continue;
+                }
+                profile::count(
+                    &REQUEST_DESCRIPTIONS,
+                    input.requests_by_endpoint[endpoint_id].len() as u64,
+                );
+
+                for &i in input.requests_by_endpoint[endpoint_id].iter() {
+                    let request_description = &input.request_descriptions[i];
+                    let video_id = request_description.video_id;
+                    if uncacheable.contains(&video_id) {
+                        continue;
+                    }
+                    // In u64, as latency times amount alone can overflow a u32. A video of size
+                    // zero costs no space, so it's ranked as if it took a single megabyte.
+                    let score = (endpoint.latency - cache_latency) as u64
+                        * request_description.weighted_amount()
+                        / input.videos[video_id].size.max(1) as u64;
+                    let candidate = candidates.entry(video_id).or_insert((0, Vec::new()));
+                    candidate.0 += score;
+                    candidate.1.push(endpoint_id);
+                }
+            }
+            candidates
+        })
+        .collect()
+}
+
+// Ranks every (cache, video) placement by the latency it saves per megabyte. Each endpoint a
+// placement would serve adds `coverage_weight` to its score, favoring placements that reach many
+// endpoints over ones that save a lot for a few. Placements scoring below `min_score` are left out of
+// the ranking, though their endpoints are still returned.
+fn density_candidates(
+    input: &Input,
+    coverage_weight: f64,
+    min_score: u64,
+) -> (Vec<(Id, Id, u64)>, CandidateEndpoints) {
+    let mut cache_latency_scores: Vec<(Id, Id, u64)> = Vec::new();
+    let mut candidate_endpoints = HashMap::new();
+    for (cache_id, candidates) in cache_candidates(input).into_iter().enumerate() {
+        for (video_id, (score, endpoint_ids)) in candidates {
+            let bonus = (coverage_weight * endpoint_ids.len() as f64).round() as u64;
+            if score + bonus >= min_score {
+                cache_latency_scores.push((cache_id, video_id, score + bonus));
+            }
+            candidate_endpoints.insert((cache_id, video_id), endpoint_ids);
+        }
+    }
+
+    sort_candidates(input, &mut cache_latency_scores);
+
+    (cache_latency_scores, candidate_endpoints)
+}
+
+// Sorts (cache, video, score) candidates best first. Among equal scores the smaller video goes
+// first, which leaves more room for the candidates after it, and remaining ties are broken by id
+// since the candidates come out of a HashMap in arbitrary order.
+fn sort_candidates(input: &Input, candidates: &mut [(Id, Id, u64)]) {
+    candidates.sort_by_key(|&(cache_id, video_id, score)| {
+        (
+            Reverse(score),
+            input.videos[video_id].size,
+            cache_id,
+            video_id,
+        )
+    });
+}
+
+// Ranks placing `video_id` in `cache_id` given the placement so far, higher first
+type Rank = dyn Fn(&Input, &State, Id, Id) -> i64;
+
+// The latency placing `video_id` in `cache_id` would save given the placement so far
+fn latency_gain(input: &Input, state: &State, cache_id: Id, video_id: Id) -> i64 {
+    input.endpoints_by_cache[cache_id]
+        .iter()
+        .map(|&endpoint_id| {
+            // Request descriptions are sorted by endpoint and then video, so each endpoint's are
+            // sorted by video
+            let requests = &input.requests_by_endpoint[endpoint_id];
+            match requests
+                .binary_search_by_key(&video_id, |&i| input.request_descriptions[i].video_id)
+            {
+                Ok(j) => {
+                    let &(_, cache_latency) = input.endpoints[endpoint_id]
+                        .cache_connections
+                        .iter()
+                        .find(|&&(id, _)| id == cache_id)
+                        .unwrap();
+                    state
+                        .latency(endpoint_id, video_id)
+                        .saturating_sub(cache_latency) as i64
+                        * input.request_descriptions[requests[j]].weighted_amount() as i64
+                }
+                Err(_) => 0,
+            }
+        })
+        .sum()
+}
+
+// The ranking `--rank <name>` selects for the density loop: `None` for its own density score,
+// which is precomputed for every candidate at once
+fn parse_rank(name: &str) -> Result<Option<Box<Rank>>, String> {
+    match name {
+        "density" => Ok(None),
+        "gain" => Ok(Some(Box::new(latency_gain))),
+        "gain-squared" => Ok(Some(Box::new(|input, state, cache_id, video_id| {
+            let gain = latency_gain(input, state, cache_id, video_id) as f64;
+            (gain * gain / input.videos[video_id].size.max(1) as f64) as i64
+        }))),
+        _ => Err(format!(
+            "unknown rank '{}', expected density, gain or gain-squared",
+            name
+        )),
+    }
+}
+
+// Under `--epsilon`, how many of the best placeable candidates a random placement picks from unless
+// `--window` says otherwise
+const EPSILON_WINDOW: usize = 8;
+
+// Under `--track-marginal`, placements saving less than this share of what their density predicted
+// are reported as misranked
+const MISRANKED: f64 = 0.5;
+
+// Where the density loop reports the placements it makes: the first `explain` are described on
+// stderr and, with `csv`, every one is written as a row of (cache, video, size, score, usage)
+#[derive(Default)]
+struct PlacementLog {
+    explain: usize,
+    explained: usize,
+    csv: Option<BufWriter<File>>,
+    // Set by `--prune`: candidates scoring below this are dropped before the loop, and how many
+    // were is reported
+    prune: Option<u64>,
+    // Set by `--rank`: scores every candidate in place of its density, and scores again those a
+    // placement affects
+    rank: Option<Box<Rank>>,
+    // Set by `--track-marginal`: every placement's actual gain is measured against the latency its
+    // density predicted, counting the placements made and those that fell short
+    track_marginal: bool,
+    tracked: usize,
+    misranked: usize,
+    // Set by `--epsilon`: the probability of placing a random candidate from the top of the ranking
+    // at each step, in `trials` runs seeded from `seed` on. The random candidate is one of the
+    // `window` best placeable ones.
+    epsilon: Option<f64>,
+    seed: u64,
+    trials: usize,
+    window: usize,
+    // Set by `--heap`: the candidates are popped off a binary heap rather than walked in order
+    heap: bool,
+    // Set by `--save-scores` and `--load-scores`: where the candidates are written once computed,
+    // or read from in place of computing them, for the input and settings in `scores_key`
+    save_scores: Option<String>,
+    load_scores: Option<String>,
+    scores_key: Option<ScoresKey>,
+}
+
+impl PlacementLog {
+    // Starts the CSV at `path` with its header row
+    fn csv<P: AsRef<Path>>(&mut self, path: P) -> io::Result<()> {
+        let mut csv = BufWriter::new(File::create(path)?);
+        writeln!(csv, "cache_id,video_id,size,score,usage")?;
+        self.csv = Some(csv);
+        Ok(())
+    }
+
+    // Reports a placement that saved `saved` milliseconds if that's less than `MISRANKED` of the
+    // latency its density `score` predicted. The density counts the latency saved against the
+    // datacenter as if no other cache held the video, so the gap is what other copies already
+    // saved, plus rounding.
+    fn marginal(&mut self, input: &Input, cache_id: Id, video_id: Id, score: u64, saved: u128) {
+        let predicted = score as u128 * input.videos[video_id].size.max(1) as u128;
+        self.tracked += 1;
+        if (saved as f64) < predicted as f64 * MISRANKED {
+            self.misranked += 1;
+            eprintln!(
+                "Misranked: cache {} video {} (score {}) predicted {}ms, saved {}ms",
+                cache_id, video_id, score, predicted, saved
+            );
+        }
+    }
+
+    fn csv_row(&mut self, state: &State, cache_id: Id, video_id: Id, score: u64) {
+        if let Some(ref mut csv) = self.csv {
+            let written = writeln!(
+                csv,
+                "{},{},{},{},{}",
+                cache_id,
+                video_id,
+                state.input.megabytes(state.input.videos[video_id].size as u64),
+                score,
+                state.input.megabytes(state.cache_usage(cache_id))
+            );
+            if let Err(err) = written {
+                eprintln!("error: cannot write CSV: {}", err);
+                process::exit(1);
+            }
+        }
+    }
+}
+
+fn density<T: Write>(
+    state: &mut State,
+    coverage_weight: f64,
+    log: &mut PlacementLog,
+    pb: &mut ProgressBar<T>,
+) {
+    let (cache_latency_scores, candidate_endpoints) = match (&log.load_scores, log.scores_key) {
+        (Some(path), Some(key)) => load_scores(path, key).unwrap_or_else(|err| {
+            eprintln!("error: cannot load scores from {}: {}", path, err);
+            process::exit(1);
+        }),
+        _ => density_candidates(state.input, coverage_weight, log.prune.unwrap_or(0)),
+    };
+    if let (Some(path), Some(key)) = (&log.save_scores, log.scores_key) {
+        save_scores(path, key, &cache_latency_scores, &candidate_endpoints).unwrap_or_else(|err| {
+            eprintln!("error: cannot write {}: {}", path, err);
+            process::exit(1);
+        });
+    }
+    if let Some(min_score) = log.prune {
+        eprintln!(
+            "Pruned {} of {} candidates scoring below {}",
+            candidate_endpoints.len() - cache_latency_scores.len(),
+            candidate_endpoints.len(),
+            min_score
+        );
+    }
+    if let Some(epsilon) = log.epsilon {
+        // Every trial fills its own copy of the placement so far, and the best is kept, the
+        // lowest seed among equal scores
+        let initial = state.clone();
+        let mut best: Option<(Score, u64)> = None;
+        for seed in log.seed..log.seed + log.trials.max(1) as u64 {
+            let mut trial = initial.clone();
+            epsilon_fill(
+                &mut trial,
+                &cache_latency_scores,
+                &candidate_endpoints,
+                epsilon,
+                log.window.max(1),
+                &mut StdRng::from_seed(&[seed as usize][..]),
+                &mut progress_bar(initial.input, sink()),
+            );
+            let score = trial.score();
+            if best.is_none_or(|(best_score, _)| score > best_score) {
+                best = Some((score, seed));
+                *state = trial;
+            }
+        }
+        if let Some((_, seed)) = best {
+            eprintln!(
+                "Epsilon {}: best of {} trials with seed {}",
+                epsilon,
+                log.trials.max(1),
+                seed
+            );
+        }
+        return;
+    }
+    if let Some(rank) = log.rank.take() {
+        ranked_fill(state, &*rank, &cache_latency_scores, &candidate_endpoints, log, pb);
+        log.rank = Some(rank);
+    } else if log.heap {
+        density_fill_heap(state, &cache_latency_scores, &candidate_endpoints, log, pb);
+    } else {
+        density_fill(state, &cache_latency_scores, &candidate_endpoints, log, pb);
+    }
+    if log.track_marginal {
+        eprintln!(
+            "Misranked {} of {} placements, saving less than {}% of their predicted latency",
+            log.misranked,
+            log.tracked,
+            MISRANKED * 100.0
+        );
+    }
+}
+
+// Whether placing `video_id` in `cache_id` fits and would improve some latency. Once it doesn't, it
+// never will again, as caches only fill up and videos only get cached in more places.
+fn placeable(
+    state: &State,
+    cache_id: Id,
+    video_id: Id,
+    candidate_endpoints: &CandidateEndpoints,
+) -> bool {
+    state.fits(cache_id, video_id)
+        && !state.cached_videos[cache_id].contains(video_id)
+        && !state.is_redundant(
+            cache_id,
+            video_id,
+            &candidate_endpoints[&(cache_id, video_id)],
+        )
+}
+
+// Like `density_fill`, but at every step, with probability `epsilon`, places a random one of the
+// next `window` placeable candidates rather than the first. Skipped candidates stay in line for later
+// steps, and a window of 1 makes exactly the placements of `density_fill`.
+fn epsilon_fill<T: Write>(
+    state: &mut State,
+    cache_latency_scores: &[(Id, Id, u64)],
+    candidate_endpoints: &CandidateEndpoints,
+    epsilon: f64,
+    window: usize,
+    rng: &mut StdRng,
+    pb: &mut ProgressBar<T>,
+) {
+    // Placed candidates and those that can no longer be placed
+    let mut done = vec![false; cache_latency_scores.len()];
+    let mut next = 0;
+    while !INTERRUPTED.load(Ordering::Relaxed) {
+        let window = if rng.gen::<f64>() < epsilon {
+            window
+        } else {
+            1
+        };
+        let mut window_candidates = Vec::with_capacity(window);
+        for i in next..cache_latency_scores.len() {
+            if window_candidates.len() == window {
+                break;
+            }
+            if done[i] {
+                continue;
+            }
+            let (cache_id, video_id, _) = cache_latency_scores[i];
+            if placeable(state, cache_id, video_id, candidate_endpoints) {
+                window_candidates.push(i);
+            } else {
+                done[i] = true;
+            }
+        }
+        if window_candidates.is_empty() {
+            return;
+        }
+
+        let i = window_candidates[rng.gen_range(0, window_candidates.len())];
+        let (cache_id, video_id, _) = cache_latency_scores[i];
+        state.insert_video_in_cache(cache_id, video_id);
+        pb.add(state.input.bytes(state.input.videos[video_id].size as u64));
+        done[i] = true;
+        while next < done.len() && done[next] {
+            next += 1;
+        }
+    }
+}
+
+// Places the candidates in order, skipping those that don't fit or wouldn't improve any latency.
+// Every placement is reported to `log`.
+fn density_fill<T: Write>(
+    state: &mut State,
+    cache_latency_scores: &[(Id, Id, u64)],
+    candidate_endpoints: &CandidateEndpoints,
+    log: &mut PlacementLog,
+    pb: &mut ProgressBar<T>,
+) {
+    // A candidate that can't be placed now never can be later: caches only fill up and videos only
+    // get cached in more places. So a single pass in score order takes, at every step, the best
+    // candidate that's still feasible, and the result doesn't depend on the number of threads.
+    // Every candidate is visited once, placed or dropped, so popping them off a heap instead, as
+    // `--heap` does, makes the same placements with a logarithmic cost per candidate on top.
+    for &(cache_id, video_id, score) in cache_latency_scores {
+        if INTERRUPTED.load(Ordering::Relaxed) {
+            return;
+        }
+        place_candidate(state, cache_id, video_id, score, candidate_endpoints, log, pb);
+    }
+}
+
+// A density candidate as `density_fill_heap` orders it, greatest first: by score, then by the
+// smallest video size, cache and video
+type HeapCandidate = (u64, Reverse<(u32, Id, Id)>);
+
+// Like `density_fill`, but pops the candidates off a binary heap ordered like `sort_candidates`
+// instead of walking them in the order given, which `--heap` selects for comparing the two loops
+fn density_fill_heap<T: Write>(
+    state: &mut State,
+    cache_latency_scores: &[(Id, Id, u64)],
+    candidate_endpoints: &CandidateEndpoints,
+    log: &mut PlacementLog,
+    pb: &mut ProgressBar<T>,
+) {
+    let input = state.input;
+    let mut heap: BinaryHeap<HeapCandidate> = cache_latency_scores
+        .iter()
+        .map(|&(cache_id, video_id, score)| {
+            (
+                score,
+                Reverse((input.videos[video_id].size, cache_id, video_id)),
+            )
+        })
+        .collect();
+    while let Some((score, Reverse((_, cache_id, video_id)))) = heap.pop() {
+        if INTERRUPTED.load(Ordering::Relaxed) {
+            return;
+        }
+        place_candidate(state, cache_id, video_id, score, candidate_endpoints, log, pb);
+    }
+}
+
+// Like `density_fill_heap`, but ranks the candidates with `rank` given the placement so far. After
+// each placement the candidates for the same video in the caches its endpoints reach are ranked
+// again, and entries left behind in the heap with an outdated rank are skipped. Ranks below zero
+// count as zero.
+fn ranked_fill<T: Write>(
+    state: &mut State,
+    rank: &Rank,
+    cache_latency_scores: &[(Id, Id, u64)],
+    candidate_endpoints: &CandidateEndpoints,
+    log: &mut PlacementLog,
+    pb: &mut ProgressBar<T>,
+) {
+    let input = state.input;
+    let rank_of = |state: &State, cache_id, video_id| {
+        rank(input, state, cache_id, video_id).max(0) as u64
+    };
+    let mut ranks: HashMap<(Id, Id), u64> = cache_latency_scores
+        .iter()
+        .map(|&(cache_id, video_id, _)| ((cache_id, video_id), rank_of(state, cache_id, video_id)))
+        .collect();
+    let mut heap: BinaryHeap<HeapCandidate> = ranks
+        .iter()
+        .map(|(&(cache_id, video_id), &rank)| {
+            (
+                rank,
+                Reverse((input.videos[video_id].size, cache_id, video_id)),
+            )
+        })
+        .collect();
+    while let Some((rank, Reverse((_, cache_id, video_id)))) = heap.pop() {
+        if INTERRUPTED.load(Ordering::Relaxed) {
+            return;
+        }
+        if ranks[&(cache_id, video_id)] != rank {
+            continue;
+        }
+        if !place_candidate(state, cache_id, video_id, rank, candidate_endpoints, log, pb) {
+            continue;
+        }
+
+        for &endpoint_id in candidate_endpoints[&(cache_id, video_id)].iter() {
+            for &(other_id, _) in input.endpoints[endpoint_id].cache_connections.iter() {
+                let current = match ranks.get_mut(&(other_id, video_id)) {
+                    Some(current) => current,
+                    None => continue,
+                };
+                let rank = rank_of(state, other_id, video_id);
+                if rank != *current {
+                    *current = rank;
+                    heap.push((
+                        rank,
+                        Reverse((input.videos[video_id].size, other_id, video_id)),
+                    ));
+                }
+            }
+        }
+    }
+}
+
+// Places `video_id` in `cache_id` for the density loops, unless it doesn't fit or wouldn't improve
+// any latency, reporting the placement to `log`. Returns whether it was placed.
+fn place_candidate<T: Write>(
+    state: &mut State,
+    cache_id: Id,
+    video_id: Id,
+    score: u64,
+    candidate_endpoints: &CandidateEndpoints,
+    log: &mut PlacementLog,
+    pb: &mut ProgressBar<T>,
+) -> bool {
+    if !placeable(state, cache_id, video_id, candidate_endpoints) {
+        return false;
+    }
+
+    // Only the endpoints that request the video through this cache can be served faster
+    let before = if log.track_marginal {
+        state
+            .score_subset(&candidate_endpoints[&(cache_id, video_id)])
+            .total_saved
+    } else {
+        0
+    };
+    if log.explained < log.explain {
+        let endpoint_ids = &candidate_endpoints[&(cache_id, video_id)];
+        let newly_served = endpoint_ids
+            .iter()
+            .filter(|&&endpoint_id| !state.is_caching(endpoint_id, video_id))
+            .count();
+        let before = state.remaining_capacity(cache_id);
+        state.insert_video_in_cache(cache_id, video_id);
+        log.explained += 1;
+        eprintln!(
+            "#{}: cache {} video {} (score {}): {}MB -> {}MB free, serves {} new of {} endpoints",
+            log.explained,
+            cache_id,
+            video_id,
+            score,
+            state.input.megabytes(before),
+            state.input.megabytes(state.remaining_capacity(cache_id)),
+            newly_served,
+            endpoint_ids.len()
+        );
+    } else {
+        state.insert_video_in_cache(cache_id, video_id);
+    }
+    if log.track_marginal {
+        let saved = state
+            .score_subset(&candidate_endpoints[&(cache_id, video_id)])
+            .total_saved
+            - before;
+        log.marginal(state.input, cache_id, video_id, score, saved);
+    }
+    log.csv_row(state, cache_id, video_id, score);
+    pb.add(state.input.bytes(state.input.videos[video_id].size as u64));
+    true
+}
+
+// Large neighborhood search: repeatedly empties a random `destroy_fraction` of the caches and refills
+// them with the density greedy, keeping the result only if the score improved. No move is ever
+// downhill, so the state returned is the best one seen without keeping a copy of it.
+fn lns(mut state: State, iterations: u64, destroy_fraction: f64, seed: u64) -> State {
+    let input = state.input;
+    let (cache_latency_scores, candidate_endpoints) = density_candidates(input, 0.0, 0);
+    let mut rng = StdRng::from_seed(&[seed as usize][..]);
+    let destroy_count = ((input.caches.len() as f64 * destroy_fraction).round() as usize)
+        .max(1)
+        .min(input.caches.len());
+
+    for _ in 0..iterations {
+        if INTERRUPTED.load(Ordering::Relaxed) {
+            break;
+        }
+        let destroyed = rand::sample(&mut rng, 0..input.caches.len(), destroy_count);
+        // Only the endpoints of the destroyed caches can be served differently after the repair
+        let mut affected: Vec<Id> = destroyed
+            .iter()
+            .flat_map(|&cache_id| input.endpoints_by_cache[cache_id].iter().cloned())
+            .collect();
+        affected.sort();
+        affected.dedup();
+        let before = state.score_subset(&affected).total_saved;
+        let previous: Vec<Vec<Id>> = destroyed
+            .iter()
+            .map(|&cache_id| state.clear_cache(cache_id))
+            .collect();
+
+        let repair: Vec<(Id, Id, u64)> = cache_latency_scores
+            .iter()
+            .filter(|&&(cache_id, _, _)| destroyed.contains(&cache_id))
+            .cloned()
+            .collect();
+        density_fill(
+            &mut state,
+            &repair,
+            &candidate_endpoints,
+            &mut PlacementLog::default(),
+            &mut progress_bar(input, sink()),
+        );
+
+        if state.score_subset(&affected).total_saved <= before {
+            for (&cache_id, videos) in destroyed.iter().zip(previous) {
+                state.clear_cache(cache_id);
+                for video_id in videos {
+                    state.insert_video_in_cache(cache_id, video_id);
+                }
+            }
+        }
+    }
+
+    state
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Strategy {
+    Greedy,
+    // Greedy's placements from a single sorted pass
+    Sorted,
+    // Greedy on the number of request descriptions served from a cache, then on latency
+    Coverage,
+    // Density that only places a video again in a cluster of caches for endpoints no copy reaches
+    Cluster,
+    // With the weight given by `--coverage-weight`, zero unless set
+    Density(f64),
+    Knapsack,
+    // Density solutions bred with `genetic::crossover`
+    Genetic,
+    // Copies of one video at a time, most demanded first
+    Video,
+    // One endpoint's requests at a time, in the order of their ids
+    Endpoint,
+    // Chosen with `--shuffle-endpoints`: the endpoints in random orders, shuffled with the seeds
+    // from the first on for as many trials as the second, keeping the best
+    ShuffledEndpoints(u64, usize),
+    // The optimum by branch and bound, for tiny inputs
+    Exact,
+    // Chosen with `--beam <width>` rather than by name
+    Beam(usize),
+    // Resolved per input by `recommend_strategy`
+    Auto,
+    // Chosen with `--reserve <fraction>`, the share of a cache below which videos are placed first
+    Reserved(f64),
+}
+
+impl FromStr for Strategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Strategy, String> {
+        match s {
+            "greedy" => Ok(Strategy::Greedy),
+            "sorted" => Ok(Strategy::Sorted),
+            "coverage" => Ok(Strategy::Coverage),
+            "cluster" => Ok(Strategy::Cluster),
+            "density" => Ok(Strategy::Density(0.0)),
+            "knapsack" => Ok(Strategy::Knapsack),
+            "genetic" => Ok(Strategy::Genetic),
+            "video" => Ok(Strategy::Video),
+            "endpoint" => Ok(Strategy::Endpoint),
+            "exact" => Ok(Strategy::Exact),
+            "auto" => Ok(Strategy::Auto),
+            _ => Err(format!(
+                "unknown strategy '{}', expected greedy, sorted, coverage, cluster, density, knapsack, genetic, video, endpoint, exact or auto",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Strategy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match *self {
+            Strategy::Greedy => "greedy",
+            Strategy::Sorted => "sorted",
+            Strategy::Coverage => "coverage",
+            Strategy::Cluster => "cluster",
+            Strategy::Density(coverage_weight) if coverage_weight != 0.0 => {
+                return write!(f, "density (coverage weight {})", coverage_weight)
+            }
+            Strategy::Density(_) => "density",
+            Strategy::Knapsack => "knapsack",
+            Strategy::Genetic => "genetic",
+            Strategy::Video => "video",
+            Strategy::Endpoint => "endpoint",
+            Strategy::Exact => "exact",
+            Strategy::ShuffledEndpoints(seed, trials) => {
+                return write!(f, "endpoint (shuffled, {} trials from seed {})", trials, seed)
+            }
+            Strategy::Beam(width) => return write!(f, "beam (width {})", width),
+            Strategy::Auto => "auto",
+            Strategy::Reserved(fraction) => {
+                return write!(f, "reserved density (small videos up to {})", fraction)
+            }
+        };
+        write!(f, "{}", name)
+    }
+}
+
+// Videos taking at least this share of a cache on average make packing each cache tightly pay off
+const KNAPSACK_SIZE_RATIO: f64 = 0.02;
+
+// Below this share of the caches reached by an average endpoint, caches serve mostly separate
+// endpoints and filling them one at a time loses little
+const KNAPSACK_CONNECTIVITY: f64 = 0.5;
+
+// The average video size as a share of the cache size, and the average share of the caches an
+// endpoint is connected to
+fn input_ratios(input: &Input) -> (f64, f64) {
+    let size_ratio = if input.videos.is_empty() || input.cache_size == 0 {
+        0.0
+    } else {
+        let total: u64 = input.videos.iter().map(|video| video.size as u64).sum();
+        total as f64 / input.videos.len() as f64 / input.cache_size as f64
+    };
+    let connectivity = if input.endpoints.is_empty() || input.caches.is_empty() {
+        0.0
+    } else {
+        let connections: usize = input
+            .endpoints
+            .iter()
+            .map(|endpoint| endpoint.cache_connections.len())
+            .sum();
+        connections as f64 / input.endpoints.len() as f64 / input.caches.len() as f64
+    };
+    (size_ratio, connectivity)
+}
+
+// Knapsack when videos are large relative to the caches or the caches barely share endpoints,
+// density otherwise. On the official inputs this picks knapsack for all but trending_today, where
+// both score within 0.03% of each other.
+fn recommend_strategy(input: &Input) -> Strategy {
+    let (size_ratio, connectivity) = input_ratios(input);
+    if size_ratio >= KNAPSACK_SIZE_RATIO || connectivity < KNAPSACK_CONNECTIVITY {
+        Strategy::Knapsack
+    } else {
+        Strategy::Density(0.0)
+    }
+}
+
+// Fills the empty `state` with `strategy`. The density strategy reports its placements to `log`.
+fn solve<'a, T: Write>(
+    mut state: State<'a>,
+    strategy: Strategy,
+    log: &mut PlacementLog,
+    pb: &mut ProgressBar<T>,
+) -> State<'a> {
+    match strategy {
+        Strategy::Greedy => greedy(&mut state, pb),
+        Strategy::Sorted => sorted_greedy(&mut state, pb),
+        Strategy::Coverage => coverage_greedy(&mut state, pb),
+        Strategy::Cluster => cluster_aware_greedy(&mut state, pb),
+        Strategy::Density(coverage_weight) => density(&mut state, coverage_weight, log, pb),
+        Strategy::Knapsack => knapsack(&mut state, pb),
+        Strategy::Genetic => genetic(&mut state),
+        Strategy::Video => video_centric_greedy(&mut state, pb),
+        Strategy::Endpoint => {
+            let order: Vec<Id> = (0..state.input.endpoints.len()).collect();
+            endpoint_greedy(&mut state, &order, pb)
+        }
+        Strategy::ShuffledEndpoints(seed, trials) => {
+            shuffled_endpoint_greedy(&mut state, seed, trials)
+        }
+        Strategy::Exact => match solve_exact(&state, EXACT_MAX_STATES) {
+            Some(exact) => state = exact,
+            None => {
+                eprintln!(
+                    "warning: no optimum within {} branches, falling back to density",
+                    EXACT_MAX_STATES
+                );
+                return solve(state, Strategy::Density(0.0), log, pb);
+            }
+        },
+        Strategy::Beam(width) => state = beam_search(&state, width),
+        Strategy::Reserved(fraction) => reserved_greedy(&mut state, fraction, pb),
+        Strategy::Auto => {
+            let strategy = recommend_strategy(state.input);
+            return solve(state, strategy, log, pb);
+        }
+    }
+    // Caches rarely pack exactly, so a finished solve completes the bar. Finishing also draws it
+    // one last time, which the refresh limit might otherwise skip.
+    if !INTERRUPTED.load(Ordering::Relaxed) {
+        pb.finish();
+    }
+    state
+}
+
+// Fills the empty `state` with `strategy` like `solve`, without logging the placements or drawing
+// a progress bar, for programs using the solvers as a library
+pub fn solve_quietly(state: State, strategy: Strategy) -> State {
+    let mut pb = progress_bar(state.input, sink());
+    solve(state, strategy, &mut PlacementLog::default(), &mut pb)
+}
+
+// How many branches `--strategy exact` searches before giving up on the input as too large
+const EXACT_MAX_STATES: u64 = 100_000;
+
+// The shortest time between two redraws of the progress bar
+const PROGRESS_REFRESH: Duration = Duration::from_millis(100);
+
+// The bar counts the megabytes placed against what could realistically be placed: each cache
+// filled with the distinct cacheable videos requested through it, up to its capacity.
+fn progress_bar<T: Write>(input: &Input, handle: T) -> ProgressBar<T> {
+    let uncacheable = input.uncacheable_videos();
+    let total: u64 = input
+        .endpoints_by_cache
+        .iter()
+        .map(|endpoint_ids| {
+            let videos: HashSet<Id> = endpoint_ids
+                .iter()
+                .flat_map(|&endpoint_id| input.requests_by_endpoint[endpoint_id].iter())
+                .map(|&request_id| input.request_descriptions[request_id].video_id)
+                .filter(|video_id| !uncacheable.contains(video_id))
+                .collect();
+            let requested: u64 = videos
+                .iter()
+                .map(|&video_id| input.videos[video_id].size as u64)
+                .sum();
+            requested.min(input.cache_size as u64)
+        })
+        .sum();
+
+    let mut pb = ProgressBar::on(handle, input.bytes(total));
+    pb.set_units(Units::Bytes);
+    // Otherwise every placement formats and writes the whole bar
+    pb.set_max_refresh_rate(Some(PROGRESS_REFRESH));
+    pb
+}
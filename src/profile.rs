@@ -0,0 +1,54 @@
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::Duration;
+
+// Set by `--profile`. Without the `profile` feature `count` is empty and the counters are never
+// touched, so the solvers pay nothing for them.
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+pub static SCORE_CALLS: AtomicU64 = AtomicU64::new(0);
+pub static IS_CACHING_CALLS: AtomicU64 = AtomicU64::new(0);
+// Full passes over the candidate placements, by `greedy_next`, the beam and the density ranking
+pub static CANDIDATE_SCANS: AtomicU64 = AtomicU64::new(0);
+pub static REQUEST_DESCRIPTIONS: AtomicU64 = AtomicU64::new(0);
+// Videos inserted into a cache that didn't hold them, by any strategy or search
+pub static PLACEMENTS: AtomicU64 = AtomicU64::new(0);
+
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+#[cfg(feature = "profile")]
+#[inline]
+pub fn count(counter: &AtomicU64, amount: u64) {
+    if ENABLED.load(Ordering::Relaxed) {
+        counter.fetch_add(amount, Ordering::Relaxed);
+    }
+}
+
+#[cfg(not(feature = "profile"))]
+#[inline(always)]
+pub fn count(_: &AtomicU64, _: u64) {}
+
+// Prints the tallies, with the placements per second of a solve that took `solving`
+pub fn print(solving: Duration) {
+    let get = |counter: &AtomicU64| counter.load(Ordering::Relaxed);
+    eprintln!(
+        "Profile: {} score calls, {} is_caching calls, {} candidate scans, {} request descriptions \
+         iterated",
+        get(&SCORE_CALLS),
+        get(&IS_CACHING_CALLS),
+        get(&CANDIDATE_SCANS),
+        get(&REQUEST_DESCRIPTIONS)
+    );
+    let placements = get(&PLACEMENTS);
+    eprintln!(
+        "Placements: {} in {:.3}s ({:.0} placements/sec)",
+        placements,
+        solving.as_secs_f64(),
+        if solving.as_secs_f64() > 0.0 {
+            placements as f64 / solving.as_secs_f64()
+        } else {
+            0.0
+        }
+    );
+}
@@ -0,0 +1,96 @@
+use std::fs;
+use std::io::{BufRead, Write};
+
+use {Id, Input, State};
+
+const HELP: &str = "commands: put <cache> <video>, rm <cache> <video>, score, show <cache>, \
+                    save <path>, help, quit";
+
+// Reads placement commands from `commands` one per line, applying them to an empty placement for
+// `input` and answering each on `out`. Commands that can't be carried out leave the placement as it
+// was and answer with an error. Returns at `quit` or the end of `commands`.
+pub fn repl<R: BufRead, W: Write>(input: &Input, commands: R, mut out: W) -> std::io::Result<()> {
+    let mut state = State::new(input);
+    for line in commands.lines() {
+        let line = line?;
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let answer = match words.as_slice() {
+            [] => continue,
+            ["quit"] | ["exit"] => break,
+            ["help"] => Ok(HELP.to_string()),
+            ["put", cache_id, video_id] => {
+                placement(&state, cache_id, video_id).and_then(|(cache_id, video_id)| {
+                    if state.cached_videos[cache_id].contains(video_id) {
+                        return Err(format!(
+                            "cache {} already holds video {}",
+                            cache_id, video_id
+                        ));
+                    }
+                    state
+                        .try_insert_video_in_cache(cache_id, video_id)
+                        .map(|()| "ok".to_string())
+                        .map_err(|err| {
+                            format!(
+                                "{}: {}MB left, {}MB needed",
+                                err,
+                                input.megabytes(state.remaining_capacity(cache_id)),
+                                input.megabytes(input.videos[video_id].size as u64)
+                            )
+                        })
+                })
+            }
+            ["rm", cache_id, video_id] => {
+                placement(&state, cache_id, video_id).and_then(|(cache_id, video_id)| {
+                    if !state.cached_videos[cache_id].contains(video_id) {
+                        return Err(format!(
+                            "cache {} doesn't hold video {}",
+                            cache_id, video_id
+                        ));
+                    }
+                    state.remove_video_from_cache(cache_id, video_id);
+                    Ok("ok".to_string())
+                })
+            }
+            ["score"] => Ok(state.score().to_string()),
+            ["show", cache_id] => cache(&state, cache_id).map(|cache_id| {
+                let mut videos: Vec<Id> = state.cached_videos[cache_id].iter().collect();
+                videos.sort();
+                let videos: Vec<String> =
+                    videos.iter().map(|video_id| video_id.to_string()).collect();
+                format!(
+                    "cache {}: {}/{}MB, videos: {}",
+                    cache_id,
+                    input.megabytes(state.cache_usage(cache_id)),
+                    input.megabytes(input.cache_size as u64),
+                    videos.join(" ")
+                )
+            }),
+            ["save", path] => fs::write(path, state.output())
+                .map(|()| format!("saved to {}", path))
+                .map_err(|err| format!("cannot write {}: {}", path, err)),
+            _ => Err(format!("unknown command '{}', {}", line.trim(), HELP)),
+        };
+        match answer {
+            Ok(answer) => writeln!(out, "{}", answer)?,
+            Err(err) => writeln!(out, "error: {}", err)?,
+        }
+    }
+    Ok(())
+}
+
+// Parses a cache id, checking that the input has that cache
+fn cache(state: &State, cache_id: &str) -> Result<Id, String> {
+    match cache_id.parse() {
+        Ok(id) if id < state.input.caches.len() => Ok(id),
+        _ => Err(format!("unknown cache {}", cache_id)),
+    }
+}
+
+// Parses a cache and a video id, checking that the input has both
+fn placement(state: &State, cache_id: &str, video_id: &str) -> Result<(Id, Id), String> {
+    let cache_id = cache(state, cache_id)?;
+    match video_id.parse() {
+        Ok(id) if id < state.input.videos.len() => Ok((cache_id, id)),
+        _ => Err(format!("unknown video {}", video_id)),
+    }
+}
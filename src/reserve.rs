@@ -0,0 +1,34 @@
+use std::io::Write;
+
+use pbr::ProgressBar;
+
+use {density_candidates, density_fill, Id, PlacementLog, State};
+
+// Density greedy in two phases, so that a few large videos can't take the room many small valuable
+// ones would have used. The first phase only places videos no larger than `reserve_fraction` of a
+// cache, the second fills the space left with every candidate.
+pub fn reserved_greedy<T: Write>(state: &mut State, reserve_fraction: f64, pb: &mut ProgressBar<T>) {
+    let input = state.input;
+    let threshold = (input.cache_size as f64 * reserve_fraction) as u64;
+    let (cache_latency_scores, candidate_endpoints) = density_candidates(input, 0.0, 0);
+
+    let small: Vec<(Id, Id, u64)> = cache_latency_scores
+        .iter()
+        .filter(|&&(_, video_id, _)| input.videos[video_id].size as u64 <= threshold)
+        .cloned()
+        .collect();
+    density_fill(
+        state,
+        &small,
+        &candidate_endpoints,
+        &mut PlacementLog::default(),
+        pb,
+    );
+    density_fill(
+        state,
+        &cache_latency_scores,
+        &candidate_endpoints,
+        &mut PlacementLog::default(),
+        pb,
+    );
+}
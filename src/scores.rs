@@ -0,0 +1,163 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use {CandidateEndpoints, Id};
+
+// The ranked placements with their scores, and the endpoints every placement would serve
+type Candidates = (Vec<(Id, Id, u64)>, CandidateEndpoints);
+
+#[derive(Debug)]
+pub enum ScoresError {
+    Io(io::Error),
+    // A line that doesn't hold what the format expects there
+    Malformed { line: usize },
+    // The scores were computed from a different input, or with different settings
+    Stale { what: &'static str },
+}
+
+impl From<io::Error> for ScoresError {
+    fn from(err: io::Error) -> ScoresError {
+        ScoresError::Io(err)
+    }
+}
+
+impl fmt::Display for ScoresError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ScoresError::Io(ref err) => write!(f, "{}", err),
+            ScoresError::Malformed { line } => write!(f, "line {}: malformed", line),
+            ScoresError::Stale { what } => {
+                write!(f, "the scores were computed for a different {}", what)
+            }
+        }
+    }
+}
+
+// What the density candidates depend on besides the placement logic, which a scores file must
+// match to be used: the input file, by its FNV-1a hash, and the settings that change the scores
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScoresKey {
+    pub checksum: u64,
+    pub coverage_weight: f64,
+    pub prune: u64,
+    pub decay: f64,
+}
+
+// The FNV-1a hash of the file at `path` as it is on disk, compressed or not
+pub fn checksum<P: AsRef<Path>>(path: P) -> io::Result<u64> {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in fs::read(path)? {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    Ok(hash)
+}
+
+// Writes the density candidates to `path`: a line with `key`, then one line per placement with its
+// cache, video, score and the endpoints it would serve. The ranked placements come first, in their
+// order, followed by those `--prune` left out, with `-` for a score, by cache and video.
+pub fn save_scores<P: AsRef<Path>>(
+    path: P,
+    key: ScoresKey,
+    cache_latency_scores: &[(Id, Id, u64)],
+    candidate_endpoints: &CandidateEndpoints,
+) -> io::Result<()> {
+    let mut buffer = format!(
+        "checksum {:016x} coverage-weight {} prune {} decay {}\n",
+        key.checksum, key.coverage_weight, key.prune, key.decay
+    );
+    let placement = |buffer: &mut String, cache_id: Id, video_id: Id, score: String| {
+        buffer.push_str(&format!("{} {} {}", cache_id, video_id, score));
+        for endpoint_id in candidate_endpoints[&(cache_id, video_id)].iter() {
+            buffer.push_str(&format!(" {}", endpoint_id));
+        }
+        buffer.push('\n');
+    };
+
+    for &(cache_id, video_id, score) in cache_latency_scores {
+        placement(&mut buffer, cache_id, video_id, score.to_string());
+    }
+    let ranked: HashSet<(Id, Id)> = cache_latency_scores
+        .iter()
+        .map(|&(cache_id, video_id, _)| (cache_id, video_id))
+        .collect();
+    let mut pruned: Vec<(Id, Id)> = candidate_endpoints
+        .keys()
+        .filter(|placement| !ranked.contains(placement))
+        .cloned()
+        .collect();
+    pruned.sort();
+    for (cache_id, video_id) in pruned {
+        placement(&mut buffer, cache_id, video_id, "-".to_string());
+    }
+
+    fs::write(path, buffer)
+}
+
+// Reads the density candidates `save_scores` wrote to `path`, refusing them unless they were
+// computed with `key`
+pub fn load_scores<P: AsRef<Path>>(path: P, key: ScoresKey) -> Result<Candidates, ScoresError> {
+    let contents = fs::read_to_string(path)?;
+    let mut lines = contents.lines();
+
+    let header: Vec<&str> = lines
+        .next()
+        .ok_or(ScoresError::Malformed { line: 1 })?
+        .split_whitespace()
+        .collect();
+    match header[..] {
+        ["checksum", checksum, "coverage-weight", coverage_weight, "prune", prune, "decay", decay] =>
+        {
+            if u64::from_str_radix(checksum, 16).ok() != Some(key.checksum) {
+                return Err(ScoresError::Stale { what: "input" });
+            }
+            if coverage_weight.parse::<f64>().ok() != Some(key.coverage_weight) {
+                return Err(ScoresError::Stale {
+                    what: "coverage weight",
+                });
+            }
+            if prune.parse::<u64>().ok() != Some(key.prune) {
+                return Err(ScoresError::Stale {
+                    what: "prune threshold",
+                });
+            }
+            if decay.parse::<f64>().ok() != Some(key.decay) {
+                return Err(ScoresError::Stale { what: "decay" });
+            }
+        }
+        _ => return Err(ScoresError::Malformed { line: 1 }),
+    }
+
+    let mut cache_latency_scores = Vec::new();
+    let mut candidate_endpoints = CandidateEndpoints::new();
+    for (i, line) in lines.enumerate() {
+        let number = i + 2;
+        let malformed = || ScoresError::Malformed { line: number };
+        let mut parts = line.split_whitespace();
+        let cache_id: Id = parts
+            .next()
+            .and_then(|part| part.parse().ok())
+            .ok_or_else(malformed)?;
+        let video_id: Id = parts
+            .next()
+            .and_then(|part| part.parse().ok())
+            .ok_or_else(malformed)?;
+        match parts.next().ok_or_else(malformed)? {
+            "-" => {}
+            score => cache_latency_scores.push((
+                cache_id,
+                video_id,
+                score.parse().map_err(|_| malformed())?,
+            )),
+        }
+        let endpoint_ids = parts
+            .map(|part| part.parse().map_err(|_| malformed()))
+            .collect::<Result<Vec<Id>, ScoresError>>()?;
+        candidate_endpoints.insert((cache_id, video_id), endpoint_ids);
+    }
+
+    Ok((cache_latency_scores, candidate_endpoints))
+}
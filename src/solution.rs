@@ -0,0 +1,336 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use {Id, Input, Score, State};
+
+#[derive(Debug, PartialEq)]
+pub enum ValidationError {
+    // A line that isn't a list of non-negative integers, or a missing cache count
+    Malformed {
+        line: usize,
+    },
+    // The leading count doesn't match the number of cache descriptions that follow
+    CacheCountMismatch {
+        declared: usize,
+        actual: usize,
+    },
+    UnknownCache {
+        line: usize,
+        cache_id: Id,
+    },
+    DuplicateCache {
+        line: usize,
+        cache_id: Id,
+    },
+    UnknownVideo {
+        line: usize,
+        video_id: Id,
+    },
+    DuplicateVideo {
+        line: usize,
+        cache_id: Id,
+        video_id: Id,
+    },
+    // In megabytes
+    CacheOverflow {
+        cache_id: Id,
+        usage: f64,
+        capacity: f64,
+    },
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ValidationError::Malformed { line } => write!(f, "line {}: malformed", line),
+            ValidationError::CacheCountMismatch { declared, actual } => write!(
+                f,
+                "{} cache descriptions declared but {} found",
+                declared, actual
+            ),
+            ValidationError::UnknownCache { line, cache_id } => {
+                write!(f, "line {}: unknown cache {}", line, cache_id)
+            }
+            ValidationError::DuplicateCache { line, cache_id } => {
+                write!(f, "line {}: cache {} already described", line, cache_id)
+            }
+            ValidationError::UnknownVideo { line, video_id } => {
+                write!(f, "line {}: unknown video {}", line, video_id)
+            }
+            ValidationError::DuplicateVideo {
+                line,
+                cache_id,
+                video_id,
+            } => write!(
+                f,
+                "line {}: video {} listed twice in cache {}",
+                line, video_id, cache_id
+            ),
+            ValidationError::CacheOverflow {
+                cache_id,
+                usage,
+                capacity,
+            } => write!(
+                f,
+                "cache {} holds {}MB but only has room for {}MB",
+                cache_id, usage, capacity
+            ),
+        }
+    }
+}
+
+// Parses a solution in the submission format into a `State`, collecting every problem that makes
+// it unacceptable to the judge.
+pub fn parse_solution<'a>(
+    input: &'a Input,
+    solution: &str,
+) -> Result<State<'a>, Vec<ValidationError>> {
+    let mut state = State::new(input);
+    let mut errors = Vec::new();
+    let mut described = HashSet::new();
+    let mut lines = solution
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line))
+        .filter(|&(_, line)| !line.trim().is_empty());
+
+    let declared: usize = match lines.next().map(|(_, line)| line.trim().parse()) {
+        Some(Ok(declared)) => declared,
+        _ => return Err(vec![ValidationError::Malformed { line: 1 }]),
+    };
+
+    for (line_number, line) in lines {
+        let ids: Result<Vec<Id>, _> = line.split_whitespace().map(|id| id.parse()).collect();
+        let ids = match ids {
+            Ok(ids) => ids,
+            Err(_) => {
+                errors.push(ValidationError::Malformed { line: line_number });
+                continue;
+            }
+        };
+
+        let cache_id = ids[0];
+        if cache_id >= input.caches.len() {
+            errors.push(ValidationError::UnknownCache {
+                line: line_number,
+                cache_id,
+            });
+            continue;
+        }
+        if !described.insert(cache_id) {
+            errors.push(ValidationError::DuplicateCache {
+                line: line_number,
+                cache_id,
+            });
+            continue;
+        }
+
+        for &video_id in ids[1..].iter() {
+            if video_id >= input.videos.len() {
+                errors.push(ValidationError::UnknownVideo {
+                    line: line_number,
+                    video_id,
+                });
+            } else if state.cached_videos[cache_id].contains(video_id) {
+                errors.push(ValidationError::DuplicateVideo {
+                    line: line_number,
+                    cache_id,
+                    video_id,
+                });
+            } else {
+                state.insert_video_in_cache(cache_id, video_id);
+            }
+        }
+    }
+
+    if declared != described.len() {
+        errors.push(ValidationError::CacheCountMismatch {
+            declared,
+            actual: described.len(),
+        });
+    }
+
+    for (cache_id, &usage) in state.cache_usage.iter().enumerate() {
+        if usage > input.cache_size as u64 {
+            errors.push(ValidationError::CacheOverflow {
+                cache_id,
+                usage: input.megabytes(usage),
+                capacity: input.megabytes(input.cache_size as u64),
+            });
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(state)
+    } else {
+        Err(errors)
+    }
+}
+
+// Removes videos listed more than once for the same cache, keeping their first occurrence. Returns
+// the cleaned solution along with a `DuplicateVideo` for every video removed. Lines that don't parse
+// are left as they are for validation to report. Only removing videos, this never increases the
+// usage of any cache.
+pub fn repair_duplicates(solution: &str) -> (String, Vec<ValidationError>) {
+    let mut repaired = String::with_capacity(solution.len());
+    let mut removed = Vec::new();
+    let mut first = true;
+
+    for (i, line) in solution.lines().enumerate() {
+        let ids: Result<Vec<Id>, _> = line.split_whitespace().map(|id| id.parse()).collect();
+        match ids {
+            Ok(ref ids) if !first && !ids.is_empty() => {
+                let cache_id = ids[0];
+                let mut seen = HashSet::new();
+                repaired.push_str(&cache_id.to_string());
+                for &video_id in ids[1..].iter() {
+                    if seen.insert(video_id) {
+                        repaired.push(' ');
+                        repaired.push_str(&video_id.to_string());
+                    } else {
+                        removed.push(ValidationError::DuplicateVideo {
+                            line: i + 1,
+                            cache_id,
+                            video_id,
+                        });
+                    }
+                }
+            }
+            _ => repaired.push_str(line),
+        }
+        if !line.trim().is_empty() {
+            first = false;
+        }
+        repaired.push('\n');
+    }
+
+    (repaired, removed)
+}
+
+// Scores a solution in the submission format, or explains why the judge would reject it
+pub fn score_solution(input: &Input, solution: &str) -> Result<Score, Vec<ValidationError>> {
+    parse_solution(input, solution).map(|state| state.score())
+}
+
+#[derive(Debug)]
+pub enum LoadError {
+    Io(io::Error),
+    // The checkpoint was written for an input with a different number of caches
+    CacheCountMismatch {
+        expected: usize,
+        found: usize,
+    },
+    Invalid(Vec<ValidationError>),
+    // The recorded usage of a cache disagrees with the sizes of the videos it holds, in megabytes
+    UsageMismatch {
+        cache_id: Id,
+        saved: f64,
+        actual: f64,
+    },
+}
+
+impl From<io::Error> for LoadError {
+    fn from(err: io::Error) -> LoadError {
+        LoadError::Io(err)
+    }
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            LoadError::Io(ref err) => write!(f, "{}", err),
+            LoadError::CacheCountMismatch { expected, found } => write!(
+                f,
+                "checkpoint has {} caches but the input has {}",
+                found, expected
+            ),
+            LoadError::Invalid(ref errors) => {
+                let errors: Vec<String> = errors.iter().map(|err| err.to_string()).collect();
+                write!(f, "{}", errors.join(", "))
+            }
+            LoadError::UsageMismatch {
+                cache_id,
+                saved,
+                actual,
+            } => write!(
+                f,
+                "cache {} was saved using {}MB but its videos take {}MB",
+                cache_id, saved, actual
+            ),
+        }
+    }
+}
+
+impl<'a> State<'a> {
+    // Checkpoints the state. The format is the submission format with each cache's usage after its
+    // id, and every cache listed, so that `load` can check it against the input.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut buffer = self.cached_videos.len().to_string();
+        buffer.push('\n');
+
+        for (cache_id, videos) in self.cached_videos.iter().enumerate() {
+            let mut videos: Vec<Id> = videos.iter().collect();
+            videos.sort();
+            buffer.push_str(&format!("{} {}", cache_id, self.cache_usage(cache_id)));
+            for video_id in videos {
+                buffer.push(' ');
+                buffer.push_str(&video_id.to_string());
+            }
+            buffer.push('\n');
+        }
+
+        fs::write(path, buffer)
+    }
+
+    // Restores a state written by `save`, validating it against `input`
+    pub fn load<P: AsRef<Path>>(input: &'a Input, path: P) -> Result<State<'a>, LoadError> {
+        let checkpoint = fs::read_to_string(path)?;
+        let mut saved_usage = Vec::new();
+        let mut solution = String::new();
+
+        for (i, line) in checkpoint.lines().enumerate() {
+            let mut parts = line.split_whitespace();
+            if i > 0 {
+                let cache_id = parts.next().unwrap_or("");
+                let usage = parts.next().and_then(|usage| usage.parse().ok());
+                match usage {
+                    Some(usage) => saved_usage.push(usage),
+                    None => {
+                        return Err(LoadError::Invalid(vec![ValidationError::Malformed {
+                            line: i + 1,
+                        }]))
+                    }
+                }
+                solution.push_str(cache_id);
+            }
+            for part in parts {
+                solution.push(' ');
+                solution.push_str(part);
+            }
+            solution.push('\n');
+        }
+
+        if saved_usage.len() != input.caches.len() {
+            return Err(LoadError::CacheCountMismatch {
+                expected: input.caches.len(),
+                found: saved_usage.len(),
+            });
+        }
+
+        let state = parse_solution(input, &solution).map_err(LoadError::Invalid)?;
+        for (cache_id, &saved) in saved_usage.iter().enumerate() {
+            if saved != state.cache_usage(cache_id) {
+                return Err(LoadError::UsageMismatch {
+                    cache_id,
+                    saved: input.megabytes(saved),
+                    actual: input.megabytes(state.cache_usage(cache_id)),
+                });
+            }
+        }
+
+        Ok(state)
+    }
+}
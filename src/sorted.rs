@@ -0,0 +1,53 @@
+use std::cmp::Reverse;
+use std::io::Write;
+use std::sync::atomic::Ordering;
+
+use pbr::ProgressBar;
+use rayon::prelude::*;
+
+use {Id, State, INTERRUPTED};
+
+// Greedy over a single sorted pass instead of a scan of every request per placement. Each request
+// description offers every cache its endpoint is connected to at the latency that cache would save
+// it, and the offers are taken best first. An offer is skipped once its endpoint already gets the
+// video from some cache, or when the video no longer fits, which leaves the slower caches of that
+// endpoint to come up later with their smaller gain. That's the choice `greedy_next` makes at every
+// step, as gains only ever shrink to zero, up to how ties are broken.
+pub fn sorted_greedy<T: Write>(state: &mut State, pb: &mut ProgressBar<T>) {
+    let input = state.input;
+    let mut offers: Vec<(u64, Id, Id, Id)> = input
+        .request_descriptions
+        .par_iter()
+        .flat_map(|request_description| {
+            let endpoint = &input.endpoints[request_description.endpoint_id];
+            endpoint
+                .faster_connections()
+                .iter()
+                .map(|&(cache_id, cache_latency)| {
+                    (
+                        (endpoint.latency - cache_latency) as u64
+                            * request_description.weighted_amount(),
+                        request_description.video_id,
+                        cache_id,
+                        request_description.endpoint_id,
+                    )
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect();
+    // Ties go to the lowest (video, cache) like in `greedy_next`
+    offers.sort_by_key(|&(gain, video_id, cache_id, endpoint_id)| {
+        (Reverse(gain), video_id, cache_id, endpoint_id)
+    });
+
+    for (_, video_id, cache_id, endpoint_id) in offers {
+        if INTERRUPTED.load(Ordering::Relaxed) {
+            return;
+        }
+        if state.is_caching(endpoint_id, video_id) || !state.fits(cache_id, video_id) {
+            continue;
+        }
+        state.insert_video_in_cache(cache_id, video_id);
+        pb.add(input.bytes(input.videos[video_id].size as u64));
+    }
+}
@@ -0,0 +1,64 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::io::Write;
+use std::sync::atomic::Ordering;
+
+use pbr::ProgressBar;
+
+use {Id, State, INTERRUPTED};
+
+// Greedy over videos rather than (cache, video) placements: videos are taken once each, most
+// demanded first, and each is copied to the cache where it saves the most latency given the copies
+// made so far, until no cache it still fits in would save any. Popular videos so get every copy
+// they're worth before any space goes to the rest.
+pub fn video_centric_greedy<T: Write>(state: &mut State, pb: &mut ProgressBar<T>) {
+    let input = state.input;
+    let mut video_ids: Vec<Id> = (0..input.videos.len()).collect();
+    // Demand in weighted requests, as that's what the latency saved is counted in
+    let demand: Vec<u64> = input
+        .requests_by_video
+        .iter()
+        .map(|requests| {
+            requests
+                .iter()
+                .map(|&i| input.request_descriptions[i].weighted_amount())
+                .sum()
+        })
+        .collect();
+    video_ids.sort_by_key(|&video_id| (Reverse(demand[video_id]), video_id));
+
+    for video_id in video_ids {
+        if INTERRUPTED.load(Ordering::Relaxed) {
+            return;
+        }
+        while let Some(cache_id) = best_cache(state, video_id) {
+            state.insert_video_in_cache(cache_id, video_id);
+            pb.add(input.bytes(input.videos[video_id].size as u64));
+        }
+    }
+}
+
+// The cache `video_id` fits in where a copy would save the most latency given the placement so far,
+// the lowest id among equals, or None if no copy would save any
+fn best_cache(state: &State, video_id: Id) -> Option<Id> {
+    let input = state.input;
+    let mut gains: HashMap<Id, u64> = HashMap::new();
+    for &i in input.requests_by_video[video_id].iter() {
+        let request_description = &input.request_descriptions[i];
+        let latency = state.latency(request_description.endpoint_id, video_id);
+        for &(cache_id, cache_latency) in input.endpoints[request_description.endpoint_id]
+            .cache_connections
+            .iter()
+        {
+            if cache_latency < latency && state.fits(cache_id, video_id) {
+                *gains.entry(cache_id).or_insert(0) +=
+                    (latency - cache_latency) as u64 * request_description.weighted_amount();
+            }
+        }
+    }
+    gains
+        .into_iter()
+        .filter(|&(_, gain)| gain > 0)
+        .max_by_key(|&(cache_id, gain)| (gain, Reverse(cache_id)))
+        .map(|(cache_id, _)| cache_id)
+}
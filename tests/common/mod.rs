@@ -0,0 +1,82 @@
+// Helpers shared by the integration tests, which run the solver binary on inputs written to the
+// temporary directory
+
+#![allow(dead_code)]
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+// Writes `contents` to a file named `name` in the temporary directory and returns its path
+pub fn write_input(name: &str, contents: &str) -> PathBuf {
+    let path = env::temp_dir().join(format!("hashcode-{}-{}.in", name, std::process::id()));
+    fs::write(&path, contents).unwrap();
+    path
+}
+
+pub fn solve(path: &PathBuf, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_hashcode"))
+        .arg(path)
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+// The videos the solution on stdout places in `cache_id`
+pub fn cached_videos(output: &Output, cache_id: usize) -> Vec<usize> {
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .skip(1)
+        .map(|line| {
+            line.split_whitespace()
+                .map(|id| id.parse().unwrap())
+                .collect::<Vec<usize>>()
+        })
+        .find(|ids| ids[0] == cache_id)
+        .map_or(Vec::new(), |ids| ids[1..].to_vec())
+}
+
+// The score line of the summary
+pub fn score(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr)
+        .lines()
+        .find(|line| line.starts_with("Score: "))
+        .expect("no score in the summary")
+        .to_string()
+}
+
+// The official example, unannotated
+pub const EXAMPLE: &str = "5 2 4 3 100
+50 50 80 30 110
+1000 3
+0 100
+2 200
+1 300
+500 0
+3 0 1500
+0 1 1000
+4 0 500
+1 0 1000
+";
+
+// Runs the solver binary with `args`, for the subcommands that don't take an input first
+pub fn run(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_hashcode"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+// The input `gen` generates with `args`
+pub fn generate(args: &[&str]) -> String {
+    let output = run(&[&["gen"], args].concat());
+    assert!(output.status.success(), "gen {:?} failed", args);
+    String::from_utf8(output.stdout).unwrap()
+}
+
+// Writes the generated input most tests solve to a file named `name`: 200 videos requested 1000
+// times from 20 endpoints, with 20 caches of 1000MB, always from seed 5
+pub fn generated_input(name: &str) -> PathBuf {
+    write_input(name, &generate(&["200", "20", "20", "1000", "1000", "5"]))
+}
@@ -0,0 +1,312 @@
+// Degenerate but legal inputs the solver must get through without panicking: zero sizes, full
+// caches, videos too large for any cache, no requests at all, sizes and savings beyond the integer
+// limits, endpoints no cache can speed up or that reach no cache at all, and caches no endpoint
+// reaches
+
+extern crate hashcode;
+
+mod common;
+
+use std::fs;
+
+use hashcode::{Cache, Endpoint, Input, Video};
+
+use common::{cached_videos, run, score, solve, write_input};
+
+#[test]
+fn zero_size_video() {
+    // Video 0 takes no space and is requested 1000 times by the only endpoint
+    let path = write_input(
+        "zero-size",
+        "2 1 2 1 100\n0 50\n1000 1\n0 10\n0 0 1000\n1 0 10\n",
+    );
+    for strategy in ["greedy", "sorted", "density", "knapsack"] {
+        let output = solve(&path, &["--strategy", strategy]);
+        assert!(output.status.success(), "{} failed", strategy);
+        assert!(
+            cached_videos(&output, 0).contains(&0),
+            "{} left the zero-size video out",
+            strategy
+        );
+    }
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn greedy_skips_full_caches() {
+    // The endpoint reaches cache 0 faster than cache 1. Once video 0 is in cache 0, video 1 no
+    // longer fits there and goes to cache 1, while the smaller video 2 still fits in what's left.
+    let path = write_input(
+        "full-cache",
+        "3 1 3 2 100\n60 60 30\n1000 2\n0 10\n1 20\n0 0 1000\n1 0 500\n2 0 100\n",
+    );
+    for strategy in ["greedy", "sorted"] {
+        let output = solve(&path, &["--strategy", strategy]);
+        assert!(output.status.success(), "{} failed", strategy);
+        let mut first = cached_videos(&output, 0);
+        first.sort();
+        assert_eq!(first, vec![0, 2], "{} filled cache 0 differently", strategy);
+        assert_eq!(
+            cached_videos(&output, 1),
+            vec![1],
+            "{} filled cache 1 differently",
+            strategy
+        );
+    }
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn savings_beyond_u64() {
+    // Each request description saves (2^32 - 1)^2ms, just below 2^64, so together they exceed it
+    let path = write_input(
+        "beyond-u64",
+        "2 1 2 1 2\n1 1\n4294967295 1\n0 0\n0 0 4294967295\n1 0 4294967295\n",
+    );
+    let output = solve(&path, &[]);
+    assert!(output.status.success());
+    let summary = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        summary.contains("Total Saved: 36893488130239234050ms"),
+        "{}",
+        summary
+    );
+    assert_eq!(score(&output), "Score: 4294967295000");
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn cache_at_the_u32_limit() {
+    // The cache holds 2^32 - 1MB and the three videos one megabyte more, so their sum only fits
+    // in 64 bits and one of them must be left out
+    let path = write_input(
+        "u32-cache",
+        "3 1 3 1 4294967295\n4294967000 295 1\n1000 1\n0 0\n0 0 1000\n1 0 1000\n2 0 1\n",
+    );
+    for strategy in ["greedy", "sorted", "density", "knapsack"] {
+        let output = solve(&path, &["--strategy", strategy, "--assert-valid"]);
+        assert!(output.status.success(), "{} failed", strategy);
+        assert_eq!(cached_videos(&output, 0).len(), 2, "{}", strategy);
+    }
+    // Greedy fills the cache to the last megabyte with the two most requested videos
+    let output = solve(&path, &["--strategy", "greedy"]);
+    assert_eq!(cached_videos(&output, 0), vec![0, 1]);
+    assert_eq!(score(&output), "Score: 999500");
+
+    let solution = path.with_extension("out");
+    fs::write(&solution, "1\n0 0 1 2\n").unwrap();
+    let output = run(&["verify", path.to_str().unwrap(), solution.to_str().unwrap()]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("cache 0 holds 4294967296MB but only has room for 4294967295MB"));
+    fs::remove_file(path).unwrap();
+    fs::remove_file(solution).unwrap();
+}
+
+#[test]
+fn zero_cache_size() {
+    // Only the zero-size videos 0 and 2 fit in caches of size zero
+    let path = write_input(
+        "zero-cache",
+        "3 1 3 2 0\n0 10 0\n1000 2\n0 100\n1 200\n0 0 10\n1 0 10\n2 0 5\n",
+    );
+    for strategy in [
+        "greedy", "sorted", "coverage", "cluster", "density", "knapsack",
+    ] {
+        let output = solve(&path, &["--strategy", strategy]);
+        assert!(output.status.success(), "{} failed", strategy);
+        assert_eq!(cached_videos(&output, 0), vec![0, 2], "{}", strategy);
+        assert_eq!(
+            cached_videos(&output, 1),
+            Vec::<usize>::new(),
+            "{}",
+            strategy
+        );
+        assert_eq!(score(&output), "Score: 540000");
+    }
+    fs::remove_file(path).unwrap();
+
+    // Nothing fits, which isn't a reason to fall back to greedy
+    let path = write_input(
+        "zero-cache-full",
+        "2 1 2 1 0\n5 10\n1000 1\n0 100\n0 0 10\n1 0 10\n",
+    );
+    let output = solve(&path, &[]);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "1\n0\n");
+    assert_eq!(score(&output), "Score: 0");
+    assert!(!String::from_utf8_lossy(&output.stderr).contains("warning"));
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn no_video_fits() {
+    // Every video is larger than the 1MB caches, so the solution lists every cache as empty
+    let path = write_input(
+        "nothing-fits",
+        "2 1 2 3 1\n5 10\n1000 2\n0 100\n2 200\n0 0 10\n1 0 20\n",
+    );
+    for strategy in ["greedy", "sorted", "density", "knapsack"] {
+        let output = solve(&path, &["--strategy", strategy]);
+        assert!(output.status.success(), "{} failed", strategy);
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout),
+            "3\n0\n1\n2\n",
+            "{}",
+            strategy
+        );
+        assert_eq!(score(&output), "Score: 0", "{}", strategy);
+    }
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn uncacheable_videos_are_never_placed() {
+    // Videos 0 and 2 are by far the most requested but larger than the 100MB caches
+    let path = write_input(
+        "uncacheable",
+        "4 1 4 2 100\n150 40 101 60\n1000 2\n0 100\n1 200\n0 0 10000\n1 0 10\n2 0 5000\n3 0 10\n",
+    );
+    for strategy in [
+        "greedy", "sorted", "coverage", "cluster", "density", "knapsack", "genetic", "video",
+        "endpoint",
+    ] {
+        let output = solve(&path, &["--strategy", strategy, "--assert-valid"]);
+        assert!(output.status.success(), "{} failed", strategy);
+        assert_eq!(cached_videos(&output, 0), vec![1, 3], "{}", strategy);
+        assert_eq!(
+            cached_videos(&output, 1),
+            Vec::<usize>::new(),
+            "{}",
+            strategy
+        );
+    }
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn no_requests() {
+    // Without a single request there is nothing to average over, which scores zero
+    let path = write_input("no-requests", "2 1 0 2 100\n5 10\n1000 1\n0 100\n");
+    let output = solve(&path, &[]);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "2\n0\n1\n");
+    assert_eq!(score(&output), "Score: 0");
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Total Saved: 0ms"));
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn endpoints_without_caches() {
+    // Endpoint 1 reaches no cache, so its 100000 requests for video 1 are served from the
+    // datacenter whatever is cached, and only count towards the number of requests
+    let path = write_input(
+        "no-connections",
+        "2 2 2 2 100\n50 50\n1000 2\n0 100\n1 200\n500 0\n0 0 10\n1 1 100000\n",
+    );
+    for strategy in [
+        "greedy", "sorted", "coverage", "cluster", "density", "knapsack", "genetic", "video",
+        "endpoint",
+    ] {
+        let output = solve(&path, &["--strategy", strategy]);
+        assert!(output.status.success(), "{} failed", strategy);
+        assert_eq!(cached_videos(&output, 0), vec![0], "{}", strategy);
+        assert_eq!(
+            cached_videos(&output, 1),
+            Vec::<usize>::new(),
+            "{}",
+            strategy
+        );
+        // 9000ms saved over 100010 requests
+        assert_eq!(score(&output), "Score: 89", "{}", strategy);
+        assert!(String::from_utf8_lossy(&output.stderr).contains("Total Saved: 9000ms"));
+    }
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn unreachable_caches_stay_empty() {
+    // The endpoint reaches caches 0 and 2 only, and requests more videos than fit in them
+    let path = write_input(
+        "unreachable",
+        "4 1 4 3 100\n50 50 50 50\n1000 2\n0 100\n2 200\n0 0 1000\n1 0 900\n2 0 800\n3 0 700\n",
+    );
+    for strategy in [
+        "greedy", "sorted", "coverage", "cluster", "density", "knapsack", "genetic", "video",
+        "endpoint",
+    ] {
+        let output = solve(&path, &["--strategy", strategy, "--verbose"]);
+        assert!(output.status.success(), "{} failed", strategy);
+        assert_eq!(cached_videos(&output, 0), vec![0, 1], "{}", strategy);
+        assert_eq!(
+            cached_videos(&output, 1),
+            Vec::<usize>::new(),
+            "{}",
+            strategy
+        );
+        assert_eq!(cached_videos(&output, 2), vec![2, 3], "{}", strategy);
+        assert!(String::from_utf8_lossy(&output.stderr)
+            .contains("warning: no endpoint is connected to caches 1\n"));
+    }
+    fs::remove_file(path).unwrap();
+
+    let input = Input::new(
+        vec![Video { size: 50 }],
+        vec![Endpoint {
+            latency: 1000,
+            cache_connections: vec![(0, 100), (2, 200)],
+        }],
+        vec![Cache {}, Cache {}, Cache {}, Cache {}],
+        100,
+        vec![],
+    );
+    assert_eq!(input.unreachable_caches(), vec![1, 3]);
+}
+
+#[test]
+fn equal_scores_place_smaller_videos_first() {
+    // Every video saves 900ms per megabyte, but after video 0 neither of the others fits
+    let path = write_input(
+        "ties",
+        "3 1 3 1 100\n60 50 50\n1000 1\n0 100\n0 0 60\n1 0 50\n2 0 50\n",
+    );
+    let output = solve(&path, &["--verbose"]);
+    assert_eq!(cached_videos(&output, 0), vec![1, 2]);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Utilization: 100/100MB"));
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn endpoints_without_faster_caches() {
+    // Endpoint 0 reaches cache 0 slower than its datacenter, so only endpoint 1 gains from it.
+    // Video 0 ends up there for endpoint 1, and endpoint 0 is still served it from the datacenter.
+    let path = write_input(
+        "useless-endpoint",
+        "2 2 3 1 100\n50 50\n100 1\n0 200\n1000 1\n0 100\n0 0 1000\n1 1 10\n0 1 5\n",
+    );
+    for strategy in [
+        "density", "greedy", "sorted", "coverage", "knapsack", "video",
+    ] {
+        let output = solve(&path, &["--strategy", strategy]);
+        assert!(output.status.success(), "{}", strategy);
+        assert_eq!(score(&output), "Score: 13300", "{}", strategy);
+    }
+    let output = solve(&path, &["--verbose"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("warning: no cache is faster than the datacenter for endpoints 0\n"),
+        "{}",
+        stderr
+    );
+    fs::remove_file(path).unwrap();
+
+    // Requested only from such an endpoint, a video is placed nowhere
+    let path = write_input(
+        "useless-endpoint-only",
+        "2 2 2 1 100\n50 50\n100 1\n0 200\n1000 1\n0 100\n0 0 1000\n1 1 10\n",
+    );
+    let output = solve(&path, &[]);
+    assert_eq!(cached_videos(&output, 0), vec![1]);
+    assert_eq!(score(&output), "Score: 8910");
+    fs::remove_file(path).unwrap();
+}
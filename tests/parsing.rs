@@ -0,0 +1,533 @@
+// Reading inputs: the flat and grouped formats, gzip, comments, fractional sizes, generated inputs,
+// and the errors reported for inputs that can't be read
+
+extern crate flate2;
+
+mod common;
+
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::Output;
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+
+use common::{cached_videos, generate, generated_input, run, score, solve, write_input, EXAMPLE};
+
+#[test]
+fn comments_and_blank_lines() {
+    let commented = "# videos, endpoints, request descriptions, caches, cache size
+5 2 4 3 100
+
+# video sizes
+50 50 80 30 110
+# endpoint 0, connected to three caches
+1000 3
+0 100
+  # the slowest cache
+2 200
+1 300
+
+# endpoint 1 has no caches
+500 0
+# requests
+3 0 1500
+0 1 1000
+
+4 0 500
+1 0 1000
+# done
+";
+    let plain = write_input("plain", EXAMPLE);
+    let annotated = write_input("annotated", commented);
+    let expected = solve(&plain, &[]);
+    let output = solve(&annotated, &[]);
+    assert!(output.status.success());
+    // Caches list their videos in arbitrary order
+    for cache_id in 0..3 {
+        let mut videos = cached_videos(&output, cache_id);
+        let mut expected_videos = cached_videos(&expected, cache_id);
+        videos.sort();
+        expected_videos.sort();
+        assert_eq!(videos, expected_videos, "cache {} differs", cache_id);
+    }
+    assert_eq!(score(&output), score(&expected));
+    fs::remove_file(plain).unwrap();
+    fs::remove_file(annotated).unwrap();
+}
+
+#[test]
+fn gzipped_inputs() {
+    let path = generated_input("plain-text");
+    let contents = fs::read(&path).unwrap();
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&contents).unwrap();
+    let compressed = encoder.finish().unwrap();
+
+    // Compressed inputs are recognized by their contents, whatever the file is called
+    let expected = solve(&path, &["--verbose"]);
+    for name in ["gzipped.in.gz", "gzipped"] {
+        let gzipped = path.with_file_name(format!("hashcode-{}-{}", std::process::id(), name));
+        fs::write(&gzipped, &compressed).unwrap();
+        let output = solve(&gzipped, &["--verbose"]);
+        assert!(output.status.success(), "{}", name);
+        assert_eq!(output.stdout, expected.stdout, "{}", name);
+        assert_eq!(score(&output), score(&expected));
+        let output = run(&["check", gzipped.to_str().unwrap()]);
+        assert!(String::from_utf8_lossy(&output.stdout)
+            .ends_with(": ok, 200 videos, 20 endpoints, 20 caches, 1000 request descriptions\n"));
+        fs::remove_file(gzipped).unwrap();
+    }
+
+    // A stream cut short is an error rather than a shorter input
+    let truncated = path.with_extension("in.gz");
+    fs::write(&truncated, &compressed[..compressed.len() / 2]).unwrap();
+    let output = solve(&truncated, &[]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(output.stdout.is_empty());
+    assert!(String::from_utf8_lossy(&output.stderr).starts_with("error: "));
+    fs::remove_file(truncated).unwrap();
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn missing_input_file() {
+    let path = env::temp_dir().join(format!("hashcode-missing-{}.in", std::process::id()));
+    let output = solve(&path, &[]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        format!(
+            "error: cannot open '{}': No such file or directory\n",
+            path.display()
+        )
+    );
+}
+
+#[test]
+fn checking_inputs() {
+    let check = |path: &PathBuf| run(&["check", path.to_str().unwrap()]);
+
+    let path = write_input("check", EXAMPLE);
+    let output = check(&path);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        format!(
+            "{}: ok, 5 videos, 2 endpoints, 3 caches, 4 request descriptions\n",
+            path.display()
+        )
+    );
+    fs::remove_file(path).unwrap();
+
+    // Cut off in the middle of the first request description
+    let path = write_input(
+        "check-truncated",
+        &EXAMPLE[..EXAMPLE.find("500 0\n3").unwrap() + 7],
+    );
+    let output = check(&path);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(output.stdout.is_empty());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        format!("error: {}: line 8: malformed\n", path.display())
+    );
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn declared_request_count() {
+    let check = |contents: &str| {
+        let path = write_input("declared", contents);
+        let output = run(&["check", path.to_str().unwrap()]);
+        fs::remove_file(path).unwrap();
+        output
+    };
+
+    assert!(check(EXAMPLE).status.success());
+
+    // Duplicates count towards the declared request descriptions even though they're merged
+    let duplicated = format!("{}1 0 1000\n", EXAMPLE.replacen("5 2 4", "5 2 5", 1));
+    let output = check(&duplicated);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).ends_with(", 4 request descriptions\n"));
+
+    let output = check(&format!("{}1 0 1000\n", EXAMPLE));
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .ends_with(": 4 request descriptions declared but 5 found\n"));
+}
+
+#[test]
+fn declared_video_count() {
+    // A file cut short on the video sizes line would otherwise parse into an input without video 4
+    let path = write_input(
+        "declared-videos",
+        &EXAMPLE.replacen("50 50 80 30 110\n", "50 50 80 30\n", 1),
+    );
+    for output in [run(&["check", path.to_str().unwrap()]), solve(&path, &[])] {
+        assert_eq!(output.status.code(), Some(1));
+        assert!(output.stdout.is_empty());
+        assert_eq!(
+            String::from_utf8_lossy(&output.stderr),
+            format!("error: {}: 5 videos declared but 4 found\n", path.display())
+        );
+    }
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn request_out_of_range() {
+    let header = "5 3 1 3 100\n50 50 80 30 110\n1000 0\n1000 1\n0 100\n1000 0\n";
+    for (name, request, error) in [
+        (
+            "undeclared-endpoint",
+            "3 999 1500\n",
+            "line 7: request for video 3 from endpoint 999 is out of range",
+        ),
+        (
+            "undeclared-video",
+            "5 1 1500\n",
+            "line 7: request for video 5 from endpoint 1 is out of range",
+        ),
+    ] {
+        let path = write_input(name, &(header.to_string() + request));
+        let output = solve(&path, &[]);
+        assert_eq!(output.status.code(), Some(1));
+        assert_eq!(
+            String::from_utf8_lossy(&output.stderr),
+            format!("error: {}: {}\n", path.display(), error)
+        );
+        fs::remove_file(path).unwrap();
+    }
+}
+
+#[test]
+fn reporting_merged_duplicates() {
+    // The 1500 requests for video 3 from endpoint 0 split over three lines
+    let duplicated = EXAMPLE
+        .replace("5 2 4 3 100", "5 2 6 3 100")
+        .replace("3 0 1500\n", "3 0 1000\n3 0 300\n3 0 200\n");
+    let path = write_input("duplicates", &duplicated);
+    let output = solve(&path, &["--verbose", "--top-contributors", "1"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains(
+            "Requests: 6 lines read, 4 request descriptions after merging 2 duplicates\n"
+        ),
+        "{}",
+        stderr
+    );
+    assert!(stderr.contains("video 3 from endpoint 0 (1500 requests)"));
+    assert_eq!(score(&output), "Score: 562500");
+    fs::remove_file(path).unwrap();
+
+    let path = write_input("no-duplicates", EXAMPLE);
+    let output = solve(&path, &["--verbose"]);
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("Requests: 4 lines read, 4 request descriptions after merging 0 duplicates\n"));
+    // Only reported with --verbose
+    let output = solve(&path, &[]);
+    assert!(!String::from_utf8_lossy(&output.stderr).contains("Requests: "));
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn grouped_input_format() {
+    let flat_path = generated_input("flat");
+    let flat = fs::read_to_string(&flat_path).unwrap();
+    let lines: Vec<&str> = flat.lines().collect();
+    let requests: usize = lines[0].split_whitespace().nth(2).unwrap().parse().unwrap();
+    let (preamble, request_lines) = lines.split_at(lines.len() - requests);
+
+    // The same request descriptions grouped by endpoint, the groups in reverse order
+    let mut groups: BTreeMap<usize, Vec<String>> = BTreeMap::new();
+    for line in request_lines {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        groups
+            .entry(fields[1].parse().unwrap())
+            .or_default()
+            .push(format!("{} {}", fields[0], fields[2]));
+    }
+    let mut grouped = preamble.join("\n") + "\n";
+    for (endpoint_id, pairs) in groups.iter().rev() {
+        grouped += &format!("{} {}\n{}\n", endpoint_id, pairs.len(), pairs.join("\n"));
+    }
+
+    let grouped_path = write_input("grouped", &grouped);
+    let expected = solve(&flat_path, &["--verbose"]);
+    let output = solve(&grouped_path, &["--verbose", "--input-format", "grouped"]);
+    assert!(output.status.success());
+    assert_eq!(output.stdout, expected.stdout);
+    assert_eq!(score(&output), score(&expected));
+    let requests_line = |output: &Output| {
+        String::from_utf8_lossy(&output.stderr)
+            .lines()
+            .find(|line| line.starts_with("Requests: "))
+            .map(str::to_string)
+    };
+    assert_eq!(requests_line(&output), requests_line(&expected));
+    assert!(requests_line(&output).is_some());
+
+    // Either format is malformed read as the other, and the format must be known
+    for (path, format) in [(&flat_path, "grouped"), (&grouped_path, "flat")] {
+        let output = solve(path, &["--input-format", format]);
+        assert_eq!(output.status.code(), Some(1), "{}", format);
+    }
+    let output = solve(&flat_path, &["--input-format", "columns"]);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "error: unknown input format 'columns', expected flat or grouped\n"
+    );
+    fs::remove_file(flat_path).unwrap();
+    fs::remove_file(grouped_path).unwrap();
+}
+
+#[test]
+fn fractional_sizes() {
+    let input = "3 1 2 1 2.75\n1.5 1 1.25\n1000 1\n0 100\n0 0 100\n2 0 90\n";
+    // Videos 0 and 2 take up the cache exactly
+    let path = write_input("fractional", input);
+    let output = solve(&path, &["--verbose"]);
+    assert_eq!(cached_videos(&output, 0), vec![0, 2]);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Cache 0: 2.75/2.75MB (100.00%)"));
+    fs::remove_file(path).unwrap();
+
+    // A thousandth of a megabyte less and only one fits
+    let path = write_input("fractional-full", &input.replace(" 2.75\n", " 2.749\n"));
+    let output = solve(&path, &[]);
+    assert_eq!(cached_videos(&output, 0), vec![2]);
+    fs::remove_file(path).unwrap();
+
+    let path = write_input("fractional-precise", &input.replace("1.25", "1.2501"));
+    let output = solve(&path, &[]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("line 2: malformed"));
+    fs::remove_file(path).unwrap();
+
+    // In thousandths of a megabyte the cache size overflows 32 bits, which is reported on the
+    // header's own line after any comments
+    let path = write_input(
+        "fractional-overflow",
+        "# a comment\n\n1 1 1 1 5000000.5\n1\n1000 1\n0 100\n0 0 10\n",
+    );
+    let output = solve(&path, &[]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("line 3: malformed"));
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn generated_inputs() {
+    let args = ["200", "20", "20", "1000", "1000"];
+    let input = generate(&[&args[..], &["5"]].concat());
+    // The same seed always generates the same input, another seed a different one
+    assert_eq!(generate(&[&args[..], &["5"]].concat()), input);
+    assert_ne!(generate(&[&args[..], &["6"]].concat()), input);
+
+    // Every cache connection is faster than the endpoint's datacenter
+    let mut lines = input.lines().skip(2);
+    for _ in 0..20 {
+        let endpoint: Vec<u32> = lines
+            .next()
+            .unwrap()
+            .split_whitespace()
+            .map(|field| field.parse().unwrap())
+            .collect();
+        for _ in 0..endpoint[1] {
+            let latency: u32 = lines
+                .next()
+                .unwrap()
+                .split_whitespace()
+                .nth(1)
+                .unwrap()
+                .parse()
+                .unwrap();
+            assert!(latency < endpoint[0]);
+        }
+    }
+
+    // Generated inputs parse with the counts they declare, and their solutions verify with the
+    // score they were solved for
+    let path = write_input("generated", &input);
+    let output = run(&["check", path.to_str().unwrap()]);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        format!(
+            "{}: ok, 200 videos, 20 endpoints, 20 caches, 1000 request descriptions\n",
+            path.display()
+        )
+    );
+    let solved = solve(&path, &[]);
+    assert!(solved.status.success());
+    let solution = path.with_extension("out");
+    fs::write(&solution, &solved.stdout).unwrap();
+    let expected = score(&solved)["Score: ".len()..].to_string();
+    let output = run(&[
+        "verify",
+        path.to_str().unwrap(),
+        solution.to_str().unwrap(),
+        "--expect",
+        &expected,
+    ]);
+    assert!(output.status.success());
+    fs::remove_file(path).unwrap();
+    fs::remove_file(solution).unwrap();
+}
+
+#[test]
+fn request_order() {
+    // The request descriptions are sorted once parsed, so neither another run nor another order
+    // of their lines changes which of equally good placements the solvers pick
+    let path = generated_input("ordered");
+    let contents = fs::read_to_string(&path).unwrap();
+    let lines: Vec<&str> = contents.lines().collect();
+    let (preamble, request_lines) = lines.split_at(lines.len() - 1000);
+    let reversed: Vec<&str> = request_lines.iter().rev().cloned().collect();
+    let reversed_path = write_input(
+        "reversed",
+        &(preamble.join("\n") + "\n" + &reversed.join("\n") + "\n"),
+    );
+
+    for strategy in ["greedy", "sorted", "density", "endpoint"] {
+        let expected = solve(&path, &["--strategy", strategy]);
+        assert!(expected.status.success(), "{} failed", strategy);
+        let again = solve(&path, &["--strategy", strategy]);
+        assert_eq!(
+            again.stdout, expected.stdout,
+            "{} differs between runs",
+            strategy
+        );
+        let output = solve(&reversed_path, &["--strategy", strategy]);
+        assert_eq!(
+            output.stdout, expected.stdout,
+            "{} differs with the lines reversed",
+            strategy
+        );
+        assert_eq!(score(&output), score(&expected));
+    }
+    fs::remove_file(path).unwrap();
+    fs::remove_file(reversed_path).unwrap();
+}
+
+#[test]
+fn parallel_parsing() {
+    // Every request line twice, so that the duplicates land in different partial maps depending on
+    // how the lines are split between threads
+    let path = generated_input("parallel");
+    let contents = fs::read_to_string(&path).unwrap();
+    let lines: Vec<&str> = contents.lines().collect();
+    let (preamble, request_lines) = lines.split_at(lines.len() - 1000);
+    let doubled = preamble.join("\n").replacen(" 1000 ", " 2000 ", 1)
+        + "\n"
+        + &request_lines.join("\n")
+        + "\n"
+        + &request_lines.join("\n")
+        + "\n";
+    fs::write(&path, &doubled).unwrap();
+
+    // The summary without the timings
+    let summary = |output: &Output| -> Vec<String> {
+        String::from_utf8_lossy(&output.stderr)
+            .lines()
+            .filter(|line| !line.starts_with("Time: ") && !line.starts_with("Phases: "))
+            .map(str::to_string)
+            .collect()
+    };
+    let serial = solve(&path, &["--threads", "1", "--verbose"]);
+    assert!(serial.status.success());
+    assert!(String::from_utf8_lossy(&serial.stderr).contains(
+        "Requests: 2000 lines read, 1000 request descriptions after merging 1000 duplicates\n"
+    ));
+    for threads in ["2", "4", "8"] {
+        let output = solve(&path, &["--threads", threads, "--verbose"]);
+        assert_eq!(output.stdout, serial.stdout, "{} threads", threads);
+        assert_eq!(summary(&output), summary(&serial), "{} threads", threads);
+    }
+
+    // Whichever thread reads it, the first malformed line is the one reported
+    let mut malformed: Vec<&str> = doubled.lines().collect();
+    malformed[preamble.len() + 10] = "1 2";
+    malformed[preamble.len() + 1900] = "x";
+    fs::write(&path, malformed.join("\n") + "\n").unwrap();
+    let expected = format!(
+        "error: {}: line {}: malformed\n",
+        path.display(),
+        preamble.len() + 11
+    );
+    for threads in ["1", "2", "4", "8"] {
+        let output = solve(&path, &["--threads", threads]);
+        assert_eq!(output.status.code(), Some(1));
+        assert_eq!(
+            String::from_utf8_lossy(&output.stderr),
+            expected,
+            "{} threads",
+            threads
+        );
+    }
+
+    // A thread count that isn't a number is refused like any other malformed flag
+    let output = solve(&path, &["--threads", "x"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "error: --threads x: invalid digit found in string\n"
+    );
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn request_indices() {
+    // The requests indexed by endpoint, which `--endpoint` lists, hold exactly the videos each
+    // endpoint's request lines ask for, and the latency they save adds up to the total saved over
+    // all request descriptions
+    let path = generated_input("indices");
+    let contents = fs::read_to_string(&path).unwrap();
+    let mut requested: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+    for line in contents.lines().skip(contents.lines().count() - 1000) {
+        let fields: Vec<usize> = line
+            .split_whitespace()
+            .map(|field| field.parse().unwrap())
+            .collect();
+        requested.entry(fields[1]).or_default().push(fields[0]);
+    }
+
+    let mut saved = 0;
+    let mut total = None;
+    for endpoint_id in 0..20 {
+        let output = solve(&path, &["--endpoint", &endpoint_id.to_string()]);
+        let summary = String::from_utf8_lossy(&output.stderr).into_owned();
+        let mut lines = summary
+            .lines()
+            .skip_while(|line| !line.starts_with(&format!("Endpoint {} ", endpoint_id)));
+        let header = lines.next().unwrap();
+        saved += header
+            .split(", ")
+            .nth(1)
+            .and_then(|part| part.strip_suffix("ms saved):"))
+            .unwrap()
+            .parse::<u64>()
+            .unwrap();
+        let videos: Vec<usize> = lines
+            .take_while(|line| line.starts_with("    video "))
+            .map(|line| {
+                line["    video ".len()..line.find(':').unwrap()]
+                    .parse()
+                    .unwrap()
+            })
+            .collect();
+        let mut expected = requested.remove(&endpoint_id).unwrap_or_default();
+        expected.sort();
+        expected.dedup();
+        assert_eq!(videos, expected, "endpoint {}", endpoint_id);
+        total = summary
+            .lines()
+            .find_map(|line| line.strip_prefix("Total Saved: "))
+            .map(|total| total.trim_end_matches("ms").parse::<u64>().unwrap());
+    }
+    assert_eq!(Some(saved), total);
+    fs::remove_file(path).unwrap();
+}
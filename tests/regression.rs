@@ -0,0 +1,141 @@
+// Pins the score the default strategy reaches on each official input, so that a change can't make
+// the solver worse without a test failing. Inputs missing from `data/` are skipped.
+
+use std::path::Path;
+use std::process::Command;
+
+// Solves `name` with the default strategy and checks the adjusted score is at least `baseline`
+fn check(name: &str, baseline: u32) {
+    check_with(name, &[], baseline);
+}
+
+// Like `check` but solves with the extra arguments `args`
+fn check_with(name: &str, args: &[&str], baseline: u32) {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("data")
+        .join(format!("{}.in", name));
+    if !path.is_file() {
+        eprintln!("skipping {}: {} is missing", name, path.display());
+        return;
+    }
+
+    let output = Command::new(env!("CARGO_BIN_EXE_hashcode"))
+        .arg(&path)
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "solving {} failed", name);
+
+    let summary = String::from_utf8_lossy(&output.stderr);
+    let score: u32 = summary
+        .split(['\r', '\n'])
+        .find_map(|line| line.strip_prefix("Score: "))
+        .expect("no score in the summary")
+        .parse()
+        .unwrap();
+    assert!(
+        score >= baseline,
+        "{} scored {}, below the baseline of {}",
+        name,
+        score,
+        baseline
+    );
+}
+
+#[test]
+fn example() {
+    check("example", 562_500);
+}
+
+#[test]
+fn me_at_the_zoo() {
+    check("me_at_the_zoo", 464_429);
+}
+
+// The video-centric greedy scores below density here, but its solution must stay valid
+#[test]
+fn me_at_the_zoo_video_centric() {
+    check_with(
+        "me_at_the_zoo",
+        &["--strategy", "video", "--assert-valid"],
+        396_493,
+    );
+}
+
+// Every strategy must produce a valid solution, each at least as good as it is now. Exact is left
+// out as it gives up on inputs this size and falls back to density.
+#[test]
+fn me_at_the_zoo_every_strategy() {
+    for &(strategy, baseline) in &[
+        ("greedy", 504_772),
+        ("sorted", 504_772),
+        ("coverage", 506_359),
+        ("cluster", 500_470),
+        ("density", 464_429),
+        ("knapsack", 485_826),
+        ("genetic", 473_442),
+        ("endpoint", 443_043),
+        ("auto", 485_826),
+    ] {
+        check_with(
+            "me_at_the_zoo",
+            &["--strategy", strategy, "--assert-valid"],
+            baseline,
+        );
+    }
+}
+
+// Filling each cache with its most requested video is a floor the greedy must stay above
+#[test]
+fn me_at_the_zoo_above_naive_baseline() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("data")
+        .join("me_at_the_zoo.in");
+    if !path.is_file() {
+        eprintln!("skipping me_at_the_zoo: {} is missing", path.display());
+        return;
+    }
+
+    let output = Command::new(env!("CARGO_BIN_EXE_hashcode"))
+        .arg("bound")
+        .arg(&path)
+        .arg("--baseline")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Empty: 0 (0ms saved in total)"));
+    let naive: u32 = stdout
+        .lines()
+        .find_map(|line| line.strip_prefix("Naive: "))
+        .and_then(|score| score.split(' ').next())
+        .expect("no naive score")
+        .parse()
+        .unwrap();
+
+    check_with("me_at_the_zoo", &["--strategy", "greedy"], naive);
+}
+
+// Pins the score 20 iterations of LNS reach from the density solution, which scores 464429
+#[test]
+fn me_at_the_zoo_lns() {
+    check_with("me_at_the_zoo", &["--lns", "20"], 474_452);
+}
+
+#[test]
+fn videos_worth_spreading() {
+    check("videos_worth_spreading", 537_382);
+}
+
+// The two largest inputs take minutes in a debug build, run them with `cargo test -- --ignored`
+#[test]
+#[ignore]
+fn trending_today() {
+    check("trending_today", 499_970);
+}
+
+#[test]
+#[ignore]
+fn kittens() {
+    check("kittens", 193_260);
+}
@@ -0,0 +1,328 @@
+// The summary, profile and reports printed alongside a solution, and the other subcommands'
+// reports
+
+mod common;
+
+use std::fs;
+use std::path::PathBuf;
+
+use common::{generate, generated_input, run, solve, write_input, EXAMPLE};
+
+#[test]
+fn redirected_stderr_has_no_progress_bar() {
+    let path = write_input("no-progress", "1 1 1 1 100\n50\n1000 1\n0 10\n0 0 1000\n");
+    let output = solve(&path, &[]);
+    assert!(output.status.success());
+    let summary = String::from_utf8_lossy(&output.stderr);
+    assert!(!summary.contains('\r'), "progress bar in {:?}", summary);
+    assert!(summary.contains("Score: "));
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn dot_export() {
+    let path = write_input("dot", EXAMPLE);
+    // The number of nodes and edges in the DOT graph of the example
+    let graph = |args: &[&str]| {
+        let output = run(&[&["dot", path.to_str().unwrap()], args].concat());
+        let dot = String::from_utf8_lossy(&output.stdout).to_string();
+        assert!(dot.starts_with("graph hashcode {\n") && dot.ends_with("}\n"));
+        let edges = dot.lines().filter(|line| line.contains(" -- ")).count();
+        let nodes = dot.lines().filter(|line| line.ends_with("];")).count() - edges;
+        (nodes, edges)
+    };
+
+    // The datacenter, 2 endpoints and 3 caches, with 3 cache connections and 2 to the datacenter
+    assert_eq!(graph(&[]), (6, 5));
+    // Endpoint 0 with only the first of its caches
+    assert_eq!(graph(&["--max-nodes", "3"]), (3, 2));
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn memory_report() {
+    // The estimated bytes held by the input at `path` and the state, removing the input
+    let memory = |path: PathBuf| {
+        let output = solve(&path, &["--mem-report"]);
+        fs::remove_file(path).unwrap();
+        let summary = String::from_utf8_lossy(&output.stderr).to_string();
+        let line = summary
+            .lines()
+            .find(|line| line.starts_with("Memory: "))
+            .expect("no memory report in the summary")
+            .to_string();
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let state = words[words.len() - 2].parse::<usize>().unwrap();
+        (words[2].parse::<usize>().unwrap(), state)
+    };
+
+    let (small_input, small_state) = memory(write_input("memory", EXAMPLE));
+    let (large_input, large_state) = memory(generated_input("memory-generated"));
+    assert!(small_input > 0 && small_state > 0);
+    assert!(
+        large_input > small_input,
+        "{} <= {}",
+        large_input,
+        small_input
+    );
+    assert!(
+        large_state > small_state,
+        "{} <= {}",
+        large_state,
+        small_state
+    );
+}
+
+#[test]
+fn top_contributors() {
+    let path = write_input("top-contributors", EXAMPLE);
+    let output = solve(&path, &["--top-contributors", "10"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let contributions: Vec<u128> = stderr
+        .lines()
+        .skip_while(|line| *line != "Top contributors:")
+        .skip(1)
+        .take_while(|line| line.starts_with("    "))
+        .map(|line| {
+            let saved = line.split(": ").nth(1).unwrap();
+            saved[..saved.find("ms").unwrap()].parse().unwrap()
+        })
+        .collect();
+    // Only the two request descriptions served from a cache contribute
+    assert_eq!(contributions, vec![1350000, 900000]);
+    let total_saved = stderr
+        .lines()
+        .find_map(|line| line.strip_prefix("Total Saved: "))
+        .unwrap();
+    assert_eq!(
+        contributions.iter().sum::<u128>().to_string() + "ms",
+        total_saved
+    );
+
+    let output = solve(&path, &["--top-contributors", "1"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains(
+        "Top contributors:\n    video 3 from endpoint 0 (1500 requests): 1350000ms (60.00%)\n"
+    ));
+    assert!(!stderr.contains("video 1 from endpoint 0"));
+    fs::remove_file(path).unwrap();
+}
+
+// Without the feature the counters are compiled out, so there would be nothing to report
+#[cfg(not(feature = "profile"))]
+#[test]
+fn profile_needs_the_feature() {
+    let path = write_input("profile-disabled", EXAMPLE);
+    let output = solve(&path, &["--profile"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "error: --profile needs a build with the profile feature\n"
+    );
+    fs::remove_file(path).unwrap();
+}
+
+#[cfg(feature = "profile")]
+#[test]
+fn profile_tallies() {
+    let path = write_input("profile", EXAMPLE);
+    let profile = |strategy: &str| {
+        let output = solve(&path, &["--strategy", strategy, "--profile"]);
+        assert!(output.status.success());
+        String::from_utf8_lossy(&output.stderr)
+            .lines()
+            .find(|line| line.starts_with("Profile: "))
+            .expect("no tallies in the profile")
+            .to_string()
+    };
+
+    // Greedy scans every candidate once per placement, twice for the example, and once more to
+    // find nothing left. Checking whether to fall back to sorted scores the solution once.
+    assert_eq!(
+        profile("greedy"),
+        "Profile: 1 score calls, 12 is_caching calls, 3 candidate scans, 20 request descriptions \
+         iterated"
+    );
+    // Density ranks its candidates in a single scan and never asks which endpoints are served
+    assert!(profile("density")
+        .starts_with("Profile: 1 score calls, 0 is_caching calls, 1 candidate scans, "));
+
+    let output = solve(&path, &[]);
+    assert!(!String::from_utf8_lossy(&output.stderr).contains("Profile: "));
+    fs::remove_file(path).unwrap();
+}
+
+#[cfg(feature = "profile")]
+#[test]
+fn placements_per_second() {
+    let path = generated_input("placements");
+    for strategy in ["greedy", "sorted", "density"] {
+        let output = solve(&path, &["--strategy", strategy, "--profile"]);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let line = stderr
+            .lines()
+            .find(|line| line.starts_with("Placements: "))
+            .expect("no placements in the profile");
+        assert!(line.ends_with(" placements/sec)"), "{}", line);
+        let placements: usize = line.split(' ').nth(1).unwrap().parse().unwrap();
+        // Every video on a cache line of the solution was inserted once
+        let placed: usize = String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .skip(1)
+            .map(|line| line.split_whitespace().count() - 1)
+            .sum();
+        assert!(placed > 0);
+        assert_eq!(placements, placed, "{}", strategy);
+    }
+
+    let output = solve(&path, &[]);
+    assert!(!String::from_utf8_lossy(&output.stderr).contains("Placements: "));
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn reporting_redundancy() {
+    // Every cache is the only one an endpoint reaches and fits one video: video 0 is copied to the
+    // three caches of the endpoints requesting it and video 1 to the fourth
+    let path = write_input(
+        "redundancy",
+        "2 4 4 4 100\n100 100\n1000 1\n0 100\n1000 1\n1 100\n1000 1\n2 100\n1000 1\n3 100\n\
+         0 0 1000\n0 1 1000\n0 2 1000\n1 3 1000\n",
+    );
+    let output = solve(&path, &["--report-redundancy"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("Redundancy: 2 distinct videos in 4 slots (2.00 copies each)\n"),
+        "{}",
+        stderr
+    );
+    let output = solve(&path, &[]);
+    assert!(!String::from_utf8_lossy(&output.stderr).contains("Redundancy: "));
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn pareto_report() {
+    let path = generated_input("pareto");
+    let output = run(&[
+        "pareto",
+        path.to_str().unwrap(),
+        "density",
+        "video",
+        "coverage",
+        "knapsack",
+        "density",
+    ]);
+    assert!(output.status.success());
+
+    // Each row's strategy, score, time and whether it's marked optimal
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let rows: Vec<(String, u64, f64, bool)> = stdout
+        .lines()
+        .skip(1)
+        .map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            (
+                fields[0].to_string(),
+                fields[1].parse().unwrap(),
+                fields[2].trim_end_matches("ms").parse().unwrap(),
+                fields.get(3) == Some(&"*"),
+            )
+        })
+        .collect();
+    let names: Vec<&str> = rows.iter().map(|row| row.0.as_str()).collect();
+    assert_eq!(
+        names,
+        ["density", "video", "coverage", "knapsack", "density"]
+    );
+    // Running the same strategy twice gives the same score however long it takes
+    assert_eq!(rows[0].1, rows[4].1);
+
+    // Marked exactly when no other row is as good on both counts and better on one
+    for &(ref name, score, time, optimal) in rows.iter() {
+        let dominated = rows.iter().any(|&(_, other, other_time, _)| {
+            other >= score && other_time <= time && (other > score || other_time < time)
+        });
+        assert_eq!(optimal, !dominated, "{}\n{}", name, stdout);
+    }
+    let best = rows.iter().map(|row| row.1).max().unwrap();
+    assert!(rows.iter().any(|row| row.1 == best && row.3), "{}", stdout);
+
+    let output = run(&["pareto", path.to_str().unwrap(), "fastest"]);
+    assert_eq!(output.status.code(), Some(1));
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn explaining_the_solution() {
+    // Endpoints 0 and 2 get video 0 from cache 0, which leaves cache 1's copy only endpoint 1
+    let path = write_input(
+        "explain-solution",
+        "1 3 3 2 50\n50\n1000 2\n0 100\n1 200\n1000 1\n1 900\n500 1\n0 400\n\
+         0 0 1000\n0 1 100\n0 2 10\n",
+    );
+    let output = solve(&path, &["--explain-solution"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains(
+            "Placements by latency saved:\n    cache 0 video 0: 901000ms, endpoints 0 2\n    \
+             cache 1 video 0: 10000ms, endpoints 1\n"
+        ),
+        "{}",
+        stderr
+    );
+    assert!(stderr.contains("Total Saved: 911000ms\n"));
+    fs::remove_file(path).unwrap();
+
+    // On a generated input the savings of the placements add up to the total
+    let path = generated_input("explain-generated");
+    let output = solve(&path, &["--explain-solution"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let saved: u128 = stderr
+        .lines()
+        .skip_while(|line| *line != "Placements by latency saved:")
+        .skip(1)
+        .take_while(|line| line.starts_with("    "))
+        .map(|line| {
+            let saved = line.split(": ").nth(1).unwrap();
+            saved[..saved.find("ms").unwrap()].parse::<u128>().unwrap()
+        })
+        .sum();
+    assert!(stderr.contains(&format!("Total Saved: {}ms\n", saved)));
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn progress_redraws_are_throttled() {
+    let path = write_input(
+        "progress",
+        &generate(&["2000", "50", "50", "20000", "10000", "1"]),
+    );
+    let output = solve(&path, &["--progress"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let frames: Vec<&str> = stderr
+        .split(['\r', '\n'])
+        .filter(|frame| frame.contains(" % "))
+        .collect();
+    let placements: usize = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .skip(1)
+        .map(|line| line.split_whitespace().count() - 1)
+        .sum();
+    // Thousands of placements in well under a second take a handful of redraws, not one each
+    assert!(
+        frames.len() * 10 < placements,
+        "{} redraws for {} placements",
+        frames.len(),
+        placements
+    );
+    // The last one is still drawn, however soon after the one before
+    let last = frames.last().unwrap();
+    assert!(last.contains(" 100.00 % "), "{}", last);
+    let counter: Vec<&str> = last.split(" [").next().unwrap().split(" / ").collect();
+    assert_eq!(counter[0], counter[1]);
+
+    let output = solve(&path, &[]);
+    assert!(!String::from_utf8_lossy(&output.stderr).contains(" % "));
+    fs::remove_file(path).unwrap();
+}
@@ -0,0 +1,468 @@
+// How solutions are scored: latency saved, normalized scores, served requests, weights and bounds,
+// how scores are ordered, and placements scored through the library
+
+extern crate hashcode;
+
+mod common;
+
+use std::collections::HashSet;
+use std::env;
+use std::fs;
+use std::process::Output;
+
+use hashcode::{
+    score_placement, solve_quietly, Cache, Endpoint, Input, RequestDescription, Score, State,
+    StateRepr, Strategy, Video,
+};
+
+use common::{cached_videos, generated_input, run, score, solve, write_input, EXAMPLE};
+
+#[test]
+fn endpoint_savings_add_up() {
+    let path = write_input("endpoint-savings", EXAMPLE);
+    let saved = |endpoint_id: &str| -> u64 {
+        let output = solve(&path, &["--endpoint", endpoint_id]);
+        let summary = String::from_utf8_lossy(&output.stderr).into_owned();
+        let line = summary
+            .lines()
+            .find(|line| line.starts_with(&format!("Endpoint {} ", endpoint_id)))
+            .unwrap()
+            .to_string();
+        line.split(", ")
+            .nth(1)
+            .unwrap()
+            .split("ms")
+            .next()
+            .unwrap()
+            .parse()
+            .unwrap()
+    };
+    assert_eq!(saved("0"), 2_250_000);
+    assert_eq!(saved("1"), 0);
+
+    let output = solve(&path, &[]);
+    let summary = String::from_utf8_lossy(&output.stderr).into_owned();
+    assert!(summary.contains("Total Saved: 2250000ms"));
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn exact_adjusted_score() {
+    // The example scores what the problem statement gives for it
+    let path = write_input("contest-example", EXAMPLE);
+    assert_eq!(score(&solve(&path, &[])), "Score: 562500");
+    fs::remove_file(path).unwrap();
+
+    // 323ms saved over 5 requests is 64600 exactly, where dividing in floating point first comes
+    // to 64599.99999999999 and would round down to 64599
+    let path = write_input(
+        "exact-score",
+        "2 1 2 1 1\n1 2\n423 1\n0 100\n0 0 1\n1 0 4\n",
+    );
+    let output = solve(&path, &[]);
+    assert_eq!(cached_videos(&output, 0), vec![0]);
+    assert_eq!(score(&output), "Score: 64600");
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn score_by_hand() {
+    // Video 0 saves 1ms on each of its 2 requests and video 1 none on its 1, so the average saved
+    // per request is 2/3ms, 666.67 microseconds, which the judge rounds down to 666
+    let input = Input::new(
+        vec![Video { size: 1 }, Video { size: 1 }],
+        vec![Endpoint {
+            latency: 1000,
+            cache_connections: vec![(0, 999)],
+        }],
+        vec![Cache {}],
+        1,
+        vec![
+            RequestDescription {
+                amount: 2,
+                video_id: 0,
+                endpoint_id: 0,
+                weight: 1.0,
+            },
+            RequestDescription {
+                amount: 1,
+                video_id: 1,
+                endpoint_id: 0,
+                weight: 1.0,
+            },
+        ],
+    );
+    let mut state = State::new(&input);
+    state.insert_video_in_cache(0, 0);
+    let score = state.score();
+    assert_eq!(score.total_saved, 2);
+    assert_eq!(score.per_request_avg_x1000, 666);
+    assert_eq!(score.to_string(), "666 (2ms saved in total)");
+}
+
+#[test]
+fn savings_beyond_u32() {
+    // Video 0 saves 3000ms on 2000000 requests, 6000000000ms in all, which wrapped around 32 bits
+    // would come to 1705032704ms and rank it below the 3000000000ms video 1 saves
+    let path = write_input(
+        "beyond-u32",
+        "2 1 2 1 1\n1 1\n3000 1\n0 0\n0 0 2000000\n1 0 1000000\n",
+    );
+    for strategy in ["density", "greedy", "sorted", "knapsack"] {
+        let output = solve(&path, &["--strategy", strategy]);
+        assert_eq!(cached_videos(&output, 0), vec![0], "{}", strategy);
+        assert_eq!(score(&output), "Score: 2000000", "{}", strategy);
+        assert!(String::from_utf8_lossy(&output.stderr).contains("Total Saved: 6000000000ms"));
+    }
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn normalized_score() {
+    let normalized = |output: &Output| {
+        String::from_utf8_lossy(&output.stderr)
+            .lines()
+            .find(|line| line.starts_with("Normalized: "))
+            .expect("no normalized score in the summary")
+            .to_string()
+    };
+
+    // 2250000ms saved of the 2700000ms that caching every video would
+    let path = write_input("normalized", EXAMPLE);
+    assert_eq!(normalized(&solve(&path, &[])), "Normalized: 0.8333");
+    fs::remove_file(path).unwrap();
+
+    // Nothing can be saved without any requests
+    let path = write_input("normalized-empty", "1 1 0 1 100\n50\n1000 1\n0 100\n");
+    assert_eq!(normalized(&solve(&path, &[])), "Normalized: 0.0000");
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn served_and_unserved_requests() {
+    // Videos 0 and 1 fill the only cache, leaving video 2 and endpoint 1, which reaches no cache,
+    // with the datacenter. 1300 of the 2000 requests are served from the cache.
+    let path = write_input(
+        "served",
+        "3 2 4 1 100\n50 50 80\n1000 1\n0 100\n500 0\n0 0 1000\n1 0 300\n2 0 200\n0 1 500\n",
+    );
+    let output = solve(&path, &[]);
+    assert_eq!(cached_videos(&output, 0), vec![0, 1]);
+    let summary = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        summary.contains(
+            "Served: 2 request descriptions, 2 unserved (65.00% of requests), 1 of 2 endpoints\n"
+        ),
+        "{}",
+        summary
+    );
+    fs::remove_file(path).unwrap();
+
+    // Without requests nothing is served, and the share is zero rather than undefined
+    let path = write_input("served-empty", "1 1 0 1 100\n50\n1000 1\n0 100\n");
+    let summary = String::from_utf8_lossy(&solve(&path, &[]).stderr).into_owned();
+    assert!(
+        summary.contains(
+            "Served: 0 request descriptions, 0 unserved (0.00% of requests), 0 of 1 endpoints"
+        ),
+        "{}",
+        summary
+    );
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn score_ordering() {
+    let new = |total_saved, per_request_avg_x1000| Score {
+        total_saved,
+        per_request_avg_x1000,
+    };
+    // The latency saved comes first, and only equal savings are told apart by the judge's score
+    let mut scores = vec![
+        new(100, 500),
+        new(300, 100),
+        new(100, 700),
+        new(200, 900),
+        new(100, 500),
+    ];
+    scores.sort_by(|a, b| b.cmp(a));
+    assert_eq!(
+        scores,
+        vec![
+            new(300, 100),
+            new(200, 900),
+            new(100, 700),
+            new(100, 500),
+            new(100, 500),
+        ]
+    );
+
+    // `solve` lists its inputs best first, by the same order, and the ones that failed last
+    let generated = generated_input("batch-generated");
+    let example = write_input("batch-example", EXAMPLE);
+    let empty = write_input("batch-empty", "1 1 1 1 1\n5\n1000 1\n0 100\n0 0 10\n");
+    let missing = env::temp_dir().join(format!("hashcode-batch-missing-{}.in", std::process::id()));
+    let output = run(&[
+        "solve",
+        missing.to_str().unwrap(),
+        empty.to_str().unwrap(),
+        example.to_str().unwrap(),
+        generated.to_str().unwrap(),
+    ]);
+    let table = String::from_utf8_lossy(&output.stdout).into_owned();
+    let order: Vec<&str> = table
+        .lines()
+        .skip(1)
+        .map(|line| line.split(' ').next().unwrap())
+        .collect();
+    assert_eq!(
+        order,
+        vec![
+            generated.to_str().unwrap(),
+            example.to_str().unwrap(),
+            empty.to_str().unwrap(),
+            missing.to_str().unwrap(),
+        ],
+        "{}",
+        table
+    );
+    for path in [generated, example, empty] {
+        fs::remove_file(path.with_extension("out")).unwrap();
+        fs::remove_file(path).unwrap();
+    }
+}
+
+#[test]
+fn fractional_bound() {
+    // The cache fits video 1 (80MB, saving 900ms * 200) and a third of video 0 (60MB, saving
+    // 900ms * 100), so the bound is 180000 + 30000, below the 270000 of serving both
+    let path = write_input(
+        "fractional-bound",
+        "2 1 2 1 100\n60 80\n1000 1\n0 100\n0 0 100\n1 0 200\n",
+    );
+    let output = run(&["bound", path.to_str().unwrap(), "--fractional"]);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "210000.00\n");
+    fs::remove_file(path).unwrap();
+
+    // A zero-size video that saves nothing, either because the cache is no faster than the
+    // datacenter or because nobody requests it, adds nothing rather than 0/0
+    for (name, contents, bound) in [
+        (
+            "fractional-bound-slow",
+            "2 1 2 1 100\n0 10\n1000 1\n0 1000\n0 0 5\n1 0 5\n",
+            "0.00\n",
+        ),
+        (
+            "fractional-bound-unrequested",
+            "2 1 2 1 100\n0 50\n1000 1\n0 100\n0 0 0\n1 0 10\n",
+            "9000.00\n",
+        ),
+    ] {
+        let path = write_input(name, contents);
+        let output = run(&["bound", path.to_str().unwrap(), "--fractional"]);
+        assert!(output.status.success(), "{}", name);
+        assert_eq!(String::from_utf8_lossy(&output.stdout), bound, "{}", name);
+        fs::remove_file(path).unwrap();
+    }
+}
+
+#[test]
+fn weighted_requests() {
+    // Only one of the videos fits, and video 0 is requested more often
+    let unweighted = "2 1 2 1 50\n50 50\n1000 1\n0 100\n0 0 100\n1 0 60\n";
+    let path = write_input("unweighted", unweighted);
+    let output = solve(&path, &[]);
+    assert_eq!(cached_videos(&output, 0), vec![0]);
+    assert_eq!(score(&output), "Score: 562500");
+    fs::remove_file(path).unwrap();
+
+    // A weight of 1 in the fourth column changes nothing
+    let path = write_input(
+        "weight-one",
+        &unweighted.replace("0 0 100\n1 0 60\n", "0 0 100 1\n1 0 60 1.0\n"),
+    );
+    let output = solve(&path, &[]);
+    assert_eq!(cached_videos(&output, 0), vec![0]);
+    assert_eq!(score(&output), "Score: 562500");
+    fs::remove_file(path).unwrap();
+
+    // Weighted twice, the 60 requests for video 1 count for 120 and take its place
+    let path = write_input("weighted", &unweighted.replace("1 0 60\n", "1 0 60 2\n"));
+    let output = solve(&path, &[]);
+    assert_eq!(cached_videos(&output, 0), vec![1]);
+    // 900ms saved for 120 weighted requests, out of 160 requests
+    assert_eq!(score(&output), "Score: 675000");
+    fs::remove_file(path).unwrap();
+
+    let path = write_input(
+        "negative-weight",
+        &unweighted.replace("1 0 60\n", "1 0 60 -1\n"),
+    );
+    let output = solve(&path, &[]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("line 6: malformed"));
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn decaying_requests() {
+    let unweighted = "2 1 2 1 50\n50 50\n1000 1\n0 100\n0 0 100\n1 0 60\n";
+
+    // Lines without an age count as fresh, so --decay leaves the usual three columns alone
+    let path = write_input("decay-legacy", unweighted);
+    let output = solve(&path, &["--decay", "0.1"]);
+    assert_eq!(cached_videos(&output, 0), vec![0]);
+    assert_eq!(score(&output), "Score: 562500");
+    fs::remove_file(path).unwrap();
+
+    // With --decay the fourth column is the age and the weight comes fifth. An age of zero leaves
+    // the amount as it is, while 7 at a rate of 0.1 scales the 60 requests down to 30.
+    let path = write_input(
+        "aged-weight",
+        &unweighted.replace("1 0 60\n", "1 0 60 0 2\n"),
+    );
+    let output = solve(&path, &["--decay", "0.1"]);
+    assert_eq!(cached_videos(&output, 0), vec![1]);
+    assert_eq!(score(&output), "Score: 675000");
+    // Without it, the line has a field too many rather than a weight of 0
+    let output = solve(&path, &[]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("line 6: malformed"));
+    fs::remove_file(path).unwrap();
+
+    let path = write_input("aged", &unweighted.replace("1 0 60\n", "1 0 60 7\n"));
+    let output = solve(&path, &["--decay", "0.1"]);
+    assert_eq!(cached_videos(&output, 0), vec![0]);
+    // 900ms saved for 100 requests, out of 130
+    assert_eq!(score(&output), "Score: 692307");
+    fs::remove_file(path).unwrap();
+
+    // Aged 20, the 100 requests for video 0 decay to 14 and video 1 takes its place. 900ms saved
+    // for 60 requests, out of 74.
+    let path = write_input("aged-out", &unweighted.replace("0 0 100\n", "0 0 100 20\n"));
+    let output = solve(&path, &["--decay", "0.1"]);
+    assert_eq!(cached_videos(&output, 0), vec![1]);
+    assert_eq!(score(&output), "Score: 729729");
+    fs::remove_file(path).unwrap();
+}
+
+// The example, built in code
+fn example() -> Input {
+    let request = |amount, video_id, endpoint_id| RequestDescription {
+        amount,
+        video_id,
+        endpoint_id,
+        weight: 1.0,
+    };
+    Input::new(
+        [50, 50, 80, 30, 110]
+            .iter()
+            .map(|&size| Video { size })
+            .collect(),
+        vec![
+            Endpoint {
+                latency: 1000,
+                cache_connections: vec![(0, 100), (2, 200), (1, 300)],
+            },
+            Endpoint {
+                latency: 500,
+                cache_connections: vec![],
+            },
+        ],
+        vec![Cache {}, Cache {}, Cache {}],
+        100,
+        vec![
+            request(1500, 3, 0),
+            request(1000, 0, 1),
+            request(500, 4, 0),
+            request(1000, 1, 0),
+        ],
+    )
+}
+
+#[test]
+fn scoring_is_idempotent() {
+    // Scoring neither changes the state nor depends on having scored it before
+    let input = example();
+    let state = solve_quietly(State::new(&input), Strategy::Density(0.0));
+    let output = state.output();
+    let first = state.score();
+    for _ in 0..5 {
+        assert_eq!(state.score(), first);
+    }
+    assert_eq!(state.output(), output);
+    assert_eq!(first.per_request_avg_x1000, 562_500);
+}
+
+#[test]
+fn effective_latencies() {
+    // Endpoint 0 reaches cache 0 in 100ms, cache 2 in 200ms and cache 1 in 300ms. Video 1 is in
+    // caches 1 and 2, so it comes from the faster one, and video 4 from the datacenter.
+    let input = example();
+    let mut state = State::new(&input);
+    state.insert_video_in_cache(0, 3);
+    state.insert_video_in_cache(1, 1);
+    state.insert_video_in_cache(2, 1);
+    let mut latencies = state.endpoint_effective_latency(0);
+    latencies.sort();
+    assert_eq!(latencies, vec![(1, 200), (3, 100), (4, 1000)]);
+    // Endpoint 1 reaches no cache
+    assert_eq!(state.endpoint_effective_latency(1), vec![(0, 500)]);
+
+    // `--endpoint` prints them for the solved example, marking the videos left to the datacenter
+    let path = write_input("effective-latencies", EXAMPLE);
+    let output = solve(&path, &["--endpoint", "0"]);
+    assert!(String::from_utf8_lossy(&output.stderr).contains(
+        "Endpoint 0 (1000ms to the datacenter, 2250000ms saved):\n    video 1: 100ms\n    video 3: \
+         100ms\n    video 4: 1000ms (datacenter)\n"
+    ));
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn scoring_placements_directly() {
+    let input = example();
+
+    // The submission from the problem statement, the density solution, one with two copies of a
+    // video and an overfilled cache, and placements listing only the first cache or none at all
+    let placements: Vec<Vec<Vec<usize>>> = vec![
+        vec![vec![2], vec![3, 1], vec![0, 1]],
+        vec![vec![1, 3], vec![], vec![]],
+        vec![vec![3], vec![3], vec![1, 4]],
+        vec![vec![0, 3]],
+        vec![],
+    ];
+    for videos in placements {
+        let placement: Vec<HashSet<usize>> = videos
+            .iter()
+            .map(|cache| cache.iter().cloned().collect())
+            .collect();
+        let mut state = State::new(&input);
+        let mut bit_set = State::with_repr(&input, StateRepr::BitSet);
+        for (cache_id, cache) in videos.iter().enumerate() {
+            for &video_id in cache {
+                state.insert_video_in_cache(cache_id, video_id);
+                bit_set.insert_video_in_cache(cache_id, video_id);
+            }
+        }
+        assert_eq!(
+            score_placement(&input, &placement),
+            state.score(),
+            "{:?}",
+            videos
+        );
+        // Either container scores the same placements alike
+        assert_eq!(bit_set.score(), state.score(), "{:?}", videos);
+    }
+
+    let statement: Vec<HashSet<usize>> = vec![
+        [2].iter().cloned().collect(),
+        [3, 1].iter().cloned().collect(),
+        [0, 1].iter().cloned().collect(),
+    ];
+    assert_eq!(
+        score_placement(&input, &statement).per_request_avg_x1000,
+        462_500
+    );
+}
@@ -0,0 +1,502 @@
+// Writing, checking and comparing solutions, listing only the nonempty caches, saving and loading
+// checkpoints, and editing solutions in the repl
+
+mod common;
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use common::{cached_videos, generated_input, run, score, solve, write_input, EXAMPLE};
+
+#[test]
+fn output_is_deterministic() {
+    let path = write_input("deterministic", EXAMPLE);
+    let first = solve(&path, &[]);
+    assert!(first.status.success());
+    for _ in 0..5 {
+        assert_eq!(solve(&path, &[]).stdout, first.stdout);
+    }
+    // Each cache lists its videos in ascending order
+    assert_eq!(String::from_utf8_lossy(&first.stdout), "3\n0 1 3\n1\n2\n");
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn listing_only_nonempty_caches() {
+    // Of the example's three caches only cache 0 holds videos
+    let path = write_input("nonempty", EXAMPLE);
+    let output = solve(&path, &["--nonempty"]);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "1\n0 1 3\n");
+    fs::remove_file(path).unwrap();
+
+    // The count matches the caches listed, which are the full solution's without the empty ones
+    let path = generated_input("nonempty-generated");
+    let full = String::from_utf8(solve(&path, &[]).stdout).unwrap();
+    let nonempty = String::from_utf8(solve(&path, &["--nonempty"]).stdout).unwrap();
+    let kept: Vec<&str> = full
+        .lines()
+        .skip(1)
+        .filter(|line| line.contains(' '))
+        .collect();
+    let mut lines = nonempty.lines();
+    assert_eq!(lines.next().unwrap(), kept.len().to_string());
+    assert_eq!(lines.collect::<Vec<_>>(), kept);
+    fs::remove_file(path).unwrap();
+
+    // Nothing fits in the 1MB caches, which leaves nothing to list
+    let path = write_input("nonempty-none", "1 1 1 2 1\n5\n1000 1\n0 100\n0 0 10\n");
+    assert_eq!(
+        String::from_utf8_lossy(&solve(&path, &["--nonempty"]).stdout),
+        "0\n"
+    );
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn annotations_match_the_solution() {
+    let sizes = [50, 50, 80, 30, 110];
+    let path = write_input("annotate", EXAMPLE);
+    let annotate = env::temp_dir().join(format!("hashcode-annotate-{}.txt", std::process::id()));
+    let output = solve(&path, &["--annotate-file", annotate.to_str().unwrap()]);
+    assert!(output.status.success());
+
+    let annotations = fs::read_to_string(&annotate).unwrap();
+    let lines: Vec<&str> = annotations.lines().collect();
+    assert_eq!(lines.len(), 3);
+    for (cache_id, line) in lines.iter().enumerate() {
+        let videos = cached_videos(&output, cache_id);
+        let usage: u32 = videos.iter().map(|&video_id| sizes[video_id]).sum();
+        assert_eq!(
+            *line,
+            format!(
+                "# cache {}: {}/100 MB, {} videos",
+                cache_id,
+                usage,
+                videos.len()
+            )
+        );
+    }
+    fs::remove_file(path).unwrap();
+    fs::remove_file(annotate).unwrap();
+}
+
+#[test]
+fn assert_valid_catches_overfilled_caches() {
+    let path = write_input("assert-valid", EXAMPLE);
+    assert!(solve(&path, &["--assert-valid"]).status.success());
+
+    // --assert-valid checks the solution with the validator verify scores it with, which refuses a
+    // hand-written solution overfilling cache 0 as it would a buggy strategy's
+    let overfilled = write_input("assert-valid-overfilled", "3\n0 0 1 2 3\n1\n2\n");
+    let output = run(&[
+        "verify",
+        path.to_str().unwrap(),
+        overfilled.to_str().unwrap(),
+    ]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(output.stdout.is_empty());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("cache 0 holds 210MB but only has room for 100MB"),
+        "{}",
+        stderr
+    );
+
+    // Nor can such a solution be solved on from
+    let output = solve(&path, &["--warm-start", overfilled.to_str().unwrap()]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("cache 0 holds 210MB but only has room for 100MB"));
+    fs::remove_file(path).unwrap();
+    fs::remove_file(overfilled).unwrap();
+}
+
+#[test]
+fn warm_starts_keep_their_videos() {
+    // Half of the caches of the video-centric solution, the others left empty
+    let path = generated_input("warm-start");
+    let mut warm = String::new();
+    for (i, line) in String::from_utf8(solve(&path, &["--strategy", "video"]).stdout)
+        .unwrap()
+        .lines()
+        .enumerate()
+    {
+        match i {
+            0 => warm.push_str(line),
+            _ if i % 2 == 1 => warm.push_str(line),
+            _ => warm.push_str(&(i - 1).to_string()),
+        }
+        warm.push('\n');
+    }
+    let solution = write_input("warm-start-solution", &warm);
+    let output = run(&["verify", path.to_str().unwrap(), solution.to_str().unwrap()]);
+    assert!(output.status.success());
+    let warm_score: u64 = String::from_utf8_lossy(&output.stdout)
+        .split(' ')
+        .next()
+        .unwrap()
+        .parse()
+        .unwrap();
+
+    for strategy in ["greedy", "density", "knapsack"] {
+        let output = solve(
+            &path,
+            &[
+                "--strategy",
+                strategy,
+                "--warm-start",
+                solution.to_str().unwrap(),
+                "--assert-valid",
+            ],
+        );
+        assert!(output.status.success(), "{} failed", strategy);
+        for line in warm.lines().skip(1) {
+            let ids: Vec<usize> = line.split(' ').map(|id| id.parse().unwrap()).collect();
+            let cached = cached_videos(&output, ids[0]);
+            for video_id in &ids[1..] {
+                assert!(
+                    cached.contains(video_id),
+                    "{} dropped video {} from cache {}",
+                    strategy,
+                    video_id,
+                    ids[0]
+                );
+            }
+        }
+        let solved: u64 = score(&output)["Score: ".len()..].parse().unwrap();
+        assert!(
+            solved >= warm_score,
+            "{}: {} < {}",
+            strategy,
+            solved,
+            warm_score
+        );
+    }
+    fs::remove_file(path).unwrap();
+    fs::remove_file(solution).unwrap();
+}
+
+#[test]
+fn verifying_claimed_scores() {
+    // The submission the problem statement gives for the example, which it scores 462500
+    let path = write_input("claimed", EXAMPLE);
+    let solution = write_input("claimed-solution", "3\n0 2\n1 3 1\n2 0 1\n");
+    let verify = |expected: &str| {
+        run(&[
+            "verify",
+            path.to_str().unwrap(),
+            solution.to_str().unwrap(),
+            "--expect",
+            expected,
+        ])
+    };
+
+    let output = verify("462500");
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "462500 (1850000ms saved in total)\n"
+    );
+
+    // Off by one is as wrong as any other score
+    for wrong in ["462501", "462499", "0"] {
+        let output = verify(wrong);
+        assert_eq!(output.status.code(), Some(1), "{}", wrong);
+        assert_eq!(
+            String::from_utf8_lossy(&output.stderr),
+            format!(
+                "error: expected a score of {} but got 462500 (1850000ms saved in total)\n",
+                wrong
+            )
+        );
+    }
+    fs::remove_file(path).unwrap();
+    fs::remove_file(solution).unwrap();
+}
+
+#[test]
+fn repairing_duplicate_videos() {
+    let path = write_input("repair", EXAMPLE);
+    let solution = write_input("repair-solution", "3\n0 2 2\n1 3 1 3\n2 0 1\n");
+    let repaired = path.with_extension("repaired");
+    let verify = |args: &[&str]| {
+        run(&[
+            &["verify", path.to_str().unwrap(), solution.to_str().unwrap()],
+            args,
+        ]
+        .concat())
+    };
+
+    // Without --repair the duplicates make the solution invalid
+    let output = verify(&[]);
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("line 2: video 2 listed twice in cache 0"));
+    assert!(stderr.contains("line 3: video 3 listed twice in cache 1"));
+
+    // With it each is reported and dropped, and the cleaned solution scores what it would have
+    let output = verify(&["--repair", repaired.to_str().unwrap()]);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "repaired: line 2: video 2 listed twice in cache 0\n\
+         repaired: line 3: video 3 listed twice in cache 1\n"
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "462500 (1850000ms saved in total)\n"
+    );
+    assert_eq!(
+        fs::read_to_string(&repaired).unwrap(),
+        "3\n0 2\n1 3 1\n2 0 1\n"
+    );
+    let output = run(&["verify", path.to_str().unwrap(), repaired.to_str().unwrap()]);
+    assert!(output.status.success());
+
+    // Dropping duplicates never frees the room an overfilled cache lacks
+    fs::write(&solution, "3\n0 2\n1 3 1\n2 0 1 3 3\n").unwrap();
+    let output = verify(&["--repair", repaired.to_str().unwrap()]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("cache 2 holds 130MB but only has room for 100MB"));
+
+    fs::remove_file(path).unwrap();
+    fs::remove_file(solution).unwrap();
+    fs::remove_file(repaired).unwrap();
+}
+
+#[test]
+fn low_memory_verification() {
+    let verify = |input: &PathBuf, solution: &PathBuf, args: &[&str]| {
+        let output = run(&[
+            &[
+                "verify",
+                input.to_str().unwrap(),
+                solution.to_str().unwrap(),
+            ],
+            args,
+        ]
+        .concat());
+        assert!(output.status.success(), "{:?}", args);
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    // Scoring the requests as they're read gives the score of loading them, with duplicates and
+    // weights that loading merges
+    let weighted = EXAMPLE
+        .replace("5 2 4 3 100", "5 2 6 3 100")
+        .replace("3 0 1500\n", "3 0 1000 0.5\n3 0 500\n")
+        + "0 1 200 3\n";
+    for (name, contents) in [
+        ("low-mem", EXAMPLE.to_string()),
+        ("low-mem-weighted", weighted),
+        (
+            "low-mem-generated",
+            fs::read_to_string(generated_input("low-mem-generated")).unwrap(),
+        ),
+    ] {
+        let input = write_input(name, &contents);
+        let solution = input.with_extension("out");
+        fs::write(&solution, solve(&input, &[]).stdout).unwrap();
+        let low_mem = verify(&input, &solution, &["--low-mem"]);
+        assert_eq!(low_mem, verify(&input, &solution, &[]), "{}", name);
+        assert!(low_mem.ends_with("ms saved in total)\n"), "{}", low_mem);
+        fs::remove_file(input).unwrap();
+        fs::remove_file(solution).unwrap();
+    }
+}
+
+// Runs the repl on the input at `path` with `commands`, returning its answers
+fn repl(path: &Path, commands: &str) -> String {
+    use std::io::Write;
+    use std::process::Stdio;
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_hashcode"))
+        .args(["repl", path.to_str().unwrap()])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(commands.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn repl_session() {
+    let path = write_input("repl", EXAMPLE);
+    assert_eq!(
+        repl(
+            &path,
+            "put 0 3\nput 0 1\nput 0 2\nput 0 3\nput 5 0\nrm 1 1\nput 1 0\nrm 1 0\nshow 0\nscore\n"
+        ),
+        "ok\n\
+         ok\n\
+         error: video 2 doesn't fit in cache 0: 20MB left, 80MB needed\n\
+         error: cache 0 already holds video 3\n\
+         error: unknown cache 5\n\
+         error: cache 1 doesn't hold video 1\n\
+         ok\n\
+         ok\n\
+         cache 0: 80/100MB, videos: 1 3\n\
+         562500 (2250000ms saved in total)\n"
+    );
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn oversized_videos_are_refused() {
+    // Video 4 is larger than any cache, so even an empty one refuses it and stays empty
+    let path = write_input("oversized", EXAMPLE);
+    assert_eq!(
+        repl(&path, "put 0 4\nshow 0\nput 0 2\nput 0 1\nshow 0\n"),
+        "error: video 4 doesn't fit in cache 0: 100MB left, 110MB needed\n\
+         cache 0: 0/100MB, videos: \n\
+         ok\n\
+         error: video 1 doesn't fit in cache 0: 20MB left, 50MB needed\n\
+         cache 0: 80/100MB, videos: 2\n"
+    );
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn compare_solutions() {
+    let input = write_input("compare", EXAMPLE);
+    let a = write_input("compare-a", "2\n0 3 1\n1 0\n");
+    let b = write_input("compare-b", "2\n0 0 3\n2 1\n");
+    let output = run(&[
+        "compare",
+        input.to_str().unwrap(),
+        a.to_str().unwrap(),
+        b.to_str().unwrap(),
+    ]);
+    assert!(output.status.success());
+    let lines: Vec<String> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|line| line.to_string())
+        .collect();
+    assert!(lines[0].ends_with(": 562500 (2250000ms saved in total)"));
+    assert!(lines[1].ends_with(": 537500 (2150000ms saved in total)"));
+    assert_eq!(
+        lines[2..],
+        [
+            "cache 0: only A: 1, only B: 0, both: 3",
+            "cache 1: only A: 0, only B: -, both: -",
+            "cache 2: only A: -, only B: 1, both: -",
+        ]
+    );
+    for path in [input, a, b] {
+        fs::remove_file(path).unwrap();
+    }
+}
+
+#[test]
+fn streamed_output_matches_saved_output() {
+    use std::io::Write;
+    use std::process::Stdio;
+
+    let path = generated_input("stream");
+    let solved = solve(&path, &[]);
+    assert!(solved.status.success());
+
+    // The solver streams its solution to stdout, while the repl saves it from a single string
+    let saved = env::temp_dir().join(format!("hashcode-stream-{}.out", std::process::id()));
+    let mut commands = String::new();
+    for line in String::from_utf8_lossy(&solved.stdout).lines().skip(1) {
+        let ids: Vec<&str> = line.split_whitespace().collect();
+        for video_id in &ids[1..] {
+            commands.push_str(&format!("put {} {}\n", ids[0], video_id));
+        }
+    }
+    commands.push_str(&format!("save {}\n", saved.display()));
+    let mut child = Command::new(env!("CARGO_BIN_EXE_hashcode"))
+        .args(["repl", path.to_str().unwrap()])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(commands.as_bytes())
+        .unwrap();
+    assert!(child.wait().unwrap().success());
+
+    assert_eq!(fs::read(&saved).unwrap(), solved.stdout);
+    fs::remove_file(saved).unwrap();
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn saved_states_load_back() {
+    let path = generated_input("checkpoint");
+    let checkpoint =
+        env::temp_dir().join(format!("hashcode-checkpoint-{}.txt", std::process::id()));
+    let saved = solve(&path, &["--save", checkpoint.to_str().unwrap()]);
+    assert!(saved.status.success());
+
+    // Loading the checkpoint reproduces the solution it was saved from, score and all
+    let loaded = solve(&path, &["--load", checkpoint.to_str().unwrap()]);
+    assert!(loaded.status.success());
+    assert_eq!(loaded.stdout, saved.stdout);
+    assert_eq!(score(&loaded), score(&saved));
+
+    // A checkpoint of another input or with edited usage is refused
+    let example = write_input("checkpoint-example", EXAMPLE);
+    let output = solve(&example, &["--load", checkpoint.to_str().unwrap()]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("checkpoint has 20 caches but the input has 3"));
+
+    let contents = fs::read_to_string(&checkpoint).unwrap();
+    let mut lines: Vec<String> = contents.lines().map(String::from).collect();
+    let mut fields: Vec<&str> = lines[1].split(' ').collect();
+    let used = format!("{}", fields[1].parse::<u32>().unwrap() - 1);
+    fields[1] = &used;
+    lines[1] = fields.join(" ");
+    fs::write(&checkpoint, lines.join("\n") + "\n").unwrap();
+    let output = solve(&path, &["--load", checkpoint.to_str().unwrap()]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("was saved using"));
+
+    fs::remove_file(checkpoint).unwrap();
+    fs::remove_file(example).unwrap();
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn closed_stdout_exits_quietly() {
+    use std::process::Stdio;
+
+    let path = generated_input("closed-stdout");
+    // Like piping into `head`, the reader is gone before the solver or the generator writes. The
+    // generated input is larger than a pipe's buffer, so it can't fit in before the reader goes.
+    for args in [
+        vec![path.to_str().unwrap()],
+        vec!["gen", "100000", "100", "100", "100000", "1000"],
+    ] {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_hashcode"))
+            .args(&args)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        drop(child.stdout.take());
+        let output = child.wait_with_output().unwrap();
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(!stderr.contains("panicked"), "{}", stderr);
+        assert!(!stderr.contains("error"), "{}", stderr);
+        assert_eq!(output.status.code(), Some(141), "{:?}", args);
+    }
+    fs::remove_file(path).unwrap();
+}
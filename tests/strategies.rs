@@ -0,0 +1,872 @@
+// What each strategy places, the options that change how it searches, how caches are clustered and
+// which caches the library offers for a placement
+
+extern crate hashcode;
+
+mod common;
+
+use std::env;
+use std::fs;
+use std::process::Output;
+
+use hashcode::{cache_clusters, Cache, Endpoint, Input, State, Video};
+
+use common::{cached_videos, generate, generated_input, score, solve, write_input, EXAMPLE};
+
+#[test]
+fn density_scores_on_the_example() {
+    // Endpoint 0 saves 900ms per request through cache 0, so video 3 (1500 requests, 30MB) scores
+    // 45000 there and video 1 (1000 requests, 50MB) 18000. The same videos in caches 1 and 2 would
+    // serve nobody new, and video 4 is larger than any cache.
+    let path = write_input("density-scores", EXAMPLE);
+    let csv = env::temp_dir().join(format!(
+        "hashcode-density-scores-{}.csv",
+        std::process::id()
+    ));
+    let output = solve(&path, &["--csv", csv.to_str().unwrap()]);
+    assert!(output.status.success());
+    assert_eq!(
+        fs::read_to_string(&csv).unwrap(),
+        "cache_id,video_id,size,score,usage\n0,3,30,45000,30\n0,1,50,18000,80\n"
+    );
+    fs::remove_file(path).unwrap();
+    fs::remove_file(csv).unwrap();
+}
+
+#[test]
+fn csv_rows_match_placements() {
+    let path = generated_input("csv-rows");
+    let csv = env::temp_dir().join(format!("hashcode-csv-rows-{}.csv", std::process::id()));
+    let output = solve(&path, &["--csv", csv.to_str().unwrap()]);
+    assert!(output.status.success());
+    let placements: usize = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .skip(1)
+        .map(|line| line.split(' ').count() - 1)
+        .sum();
+    assert!(placements > 0);
+    let contents = fs::read_to_string(&csv).unwrap();
+    let mut rows = contents.lines();
+    assert_eq!(rows.next(), Some("cache_id,video_id,size,score,usage"));
+    assert_eq!(rows.count(), placements);
+    fs::remove_file(path).unwrap();
+    fs::remove_file(csv).unwrap();
+}
+
+#[test]
+fn density_skips_redundant_copies() {
+    // Endpoint 0 reaches cache 0 in 100ms and cache 1 in 200ms, endpoint 1 only cache 1. Once video
+    // 0 is in cache 0, a copy in cache 1 still ranks above everything else, but would only serve
+    // endpoint 0 slower than the copy it already gets. Video 1 goes to cache 1 for endpoint 1.
+    let path = write_input(
+        "redundant-copies",
+        "2 2 3 2 100\n50 50\n1000 2\n0 100\n1 200\n1000 1\n1 300\n0 0 1000\n1 0 10\n1 1 10\n",
+    );
+    let output = solve(&path, &["--verbose"]);
+    assert!(output.status.success());
+    assert_eq!(cached_videos(&output, 0), vec![0, 1]);
+    assert_eq!(cached_videos(&output, 1), vec![1]);
+    // Cache 1 has room for video 0 but is left half empty
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Cache 1: 50/100MB (50.00%)"));
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn backends_agree() {
+    // The solution lists each cache's videos in ascending order whichever container holds them, so
+    // the full solutions must match byte for byte
+    for path in [
+        write_input("backends", EXAMPLE),
+        generated_input("backends-generated"),
+    ] {
+        for strategy in ["density", "knapsack"] {
+            let hash_set = solve(&path, &["--strategy", strategy]);
+            let bit_set = solve(&path, &["--strategy", strategy, "--bitset"]);
+            assert!(hash_set.status.success() && bit_set.status.success());
+            assert!(!hash_set.stdout.is_empty());
+            assert_eq!(
+                String::from_utf8_lossy(&hash_set.stdout),
+                String::from_utf8_lossy(&bit_set.stdout),
+                "{}",
+                strategy
+            );
+            assert_eq!(score(&hash_set), score(&bit_set), "{}", strategy);
+        }
+        fs::remove_file(path).unwrap();
+    }
+}
+
+#[test]
+fn heap_and_sorted_walk_agree() {
+    // Popping the candidates off a heap takes them in the order of the sorted walk, so both make
+    // the same placements
+    for path in [
+        write_input("heap", EXAMPLE),
+        generated_input("heap-generated"),
+    ] {
+        for args in [&[][..], &["--coverage-weight", "500"], &["--rank", "gain"]] {
+            let sorted = solve(&path, args);
+            let heap = solve(&path, &[args, &["--heap"]].concat());
+            assert!(heap.status.success(), "{:?}", args);
+            assert_eq!(
+                String::from_utf8_lossy(&heap.stdout),
+                String::from_utf8_lossy(&sorted.stdout),
+                "{:?}",
+                args
+            );
+            assert_eq!(score(&heap), score(&sorted), "{:?}", args);
+        }
+        fs::remove_file(path).unwrap();
+    }
+
+    let path = write_input("heap-greedy", EXAMPLE);
+    let output = solve(&path, &["--strategy", "greedy", "--heap"]);
+    assert_eq!(output.status.code(), Some(1));
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn worthless_solutions_fall_back_to_sorted() {
+    let fallback = |output: &Output, strategy: &str| {
+        String::from_utf8_lossy(&output.stderr).contains(&format!(
+            "warning: the {} strategy saved nothing, falling back to sorted",
+            strategy
+        ))
+    };
+
+    // A beam of width zero never keeps a state, so it places nothing
+    let path = write_input("fallback", EXAMPLE);
+    let output = solve(&path, &["--beam", "0"]);
+    assert!(output.status.success());
+    assert!(fallback(&output, "beam (width 0)"));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Strategy: sorted"));
+    assert_eq!(score(&output), "Score: 562500");
+    fs::remove_file(path).unwrap();
+
+    // The only request saves 1ms through cache 0, which rounds to a density of zero per megabyte
+    // of the 1000MB video. Density still places zero scores, but pruned there's nothing left.
+    let path = write_input("fallback-rounding", "1 1 1 1 1000\n1000\n2 1\n0 1\n0 0 1\n");
+    let output = solve(&path, &[]);
+    assert!(!fallback(&output, "density"));
+    assert_eq!(score(&output), "Score: 1000");
+    let output = solve(&path, &["--prune", "1"]);
+    assert!(output.status.success());
+    assert!(fallback(&output, "density"));
+    assert_eq!(cached_videos(&output, 0), vec![0]);
+    assert_eq!(score(&output), "Score: 1000");
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn pruning_candidates() {
+    let path = write_input("prune", EXAMPLE);
+    let unpruned = solve(&path, &[]);
+
+    let output = solve(&path, &["--prune", "0"]);
+    let summary = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        summary.contains("Pruned 0 of 6 candidates scoring below 0"),
+        "{}",
+        summary
+    );
+    assert_eq!(output.stdout, unpruned.stdout);
+
+    // Video 1 scores 18000 at most, so only video 3 is left to place
+    let output = solve(&path, &["--prune", "20000"]);
+    let summary = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        summary.contains("Pruned 3 of 6 candidates scoring below 20000"),
+        "{}",
+        summary
+    );
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "3\n0 3\n1\n2\n");
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn coverage_serves_more_requests() {
+    // Video 0 saves the most latency per MB but fills the cache for a single request description,
+    // while video 1 serves two
+    let path = write_input(
+        "coverage",
+        "2 3 3 1 100
+100 50
+1000 1
+0 100
+1000 1
+0 100
+1000 1
+0 100
+0 0 1000
+1 1 1
+1 2 1
+",
+    );
+    let output = solve(&path, &["--strategy", "density"]);
+    assert_eq!(cached_videos(&output, 0), vec![0]);
+    let summary = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        summary.contains("Served: 1 request descriptions"),
+        "{}",
+        summary
+    );
+
+    let output = solve(&path, &["--strategy", "coverage"]);
+    assert_eq!(cached_videos(&output, 0), vec![1]);
+    let summary = String::from_utf8_lossy(&output.stderr);
+    assert!(summary.contains("Strategy: coverage"), "{}", summary);
+    assert!(
+        summary.contains("Served: 2 request descriptions"),
+        "{}",
+        summary
+    );
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn coverage_weight_serves_more_endpoints() {
+    // Video 0 scores 900ms * 1000 / 100MB = 9000 for endpoint 0 alone and video 1 2 * 900ms / 50MB
+    // = 36 for endpoints 1 and 2, so a bonus of more than 8964 per endpoint makes video 1 win
+    let path = write_input(
+        "coverage-weight",
+        "2 3 3 1 100
+100 50
+1000 1
+0 100
+1000 1
+0 100
+1000 1
+0 100
+0 0 1000
+1 1 1
+1 2 1
+",
+    );
+    let served = |weight: &str| {
+        let output = solve(&path, &["--coverage-weight", weight]);
+        assert!(output.status.success());
+        let summary = String::from_utf8_lossy(&output.stderr).into_owned();
+        let line = summary
+            .lines()
+            .find(|line| line.starts_with("Served: "))
+            .unwrap()
+            .to_string();
+        (cached_videos(&output, 0), line)
+    };
+    let (videos, line) = served("0");
+    assert_eq!(videos, vec![0]);
+    assert!(line.ends_with(", 1 of 3 endpoints"), "{}", line);
+    assert_eq!(served("5000").0, vec![0]);
+    let (videos, line) = served("10000");
+    assert_eq!(videos, vec![1]);
+    assert!(line.ends_with(", 2 of 3 endpoints"), "{}", line);
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn sweeping_lns_seeds() {
+    let path = generated_input("sweep");
+    // The score after LNS and the seed it ran with
+    let lns = |args: &[&str]| {
+        let output = solve(
+            &path,
+            &[&["--strategy", "greedy", "--lns", "5"], args].concat(),
+        );
+        let summary = String::from_utf8_lossy(&output.stderr).to_string();
+        let line = summary
+            .lines()
+            .find(|line| line.starts_with("LNS: "))
+            .expect("no LNS line in the summary");
+        let words: Vec<&str> = line.split_whitespace().collect();
+        (
+            words[3].parse::<u64>().unwrap(),
+            words.last().unwrap().to_string(),
+        )
+    };
+
+    let (single, _) = lns(&["--seed", "0"]);
+    let (best, seed) = lns(&["--sweep-seeds", "4"]);
+    assert!(best >= single, "{} < {}", best, single);
+    // The winning seed reproduces the sweep's result on its own
+    assert_eq!(lns(&["--seed", &seed]), (best, seed.clone()));
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn cluster_avoids_redundant_copies() {
+    // Endpoint 1 only reaches cache 1, so video 0 goes there first. Another copy in cache 0 would
+    // only speed endpoint 0 up from 200ms to 100ms, where video 1 reaches it from nowhere else.
+    let path = write_input(
+        "cluster",
+        "2 2 3 2 50
+50 50
+1000 2
+0 100
+1 200
+1000 1
+1 100
+0 0 1000
+1 0 10
+0 1 1000
+",
+    );
+    let output = solve(&path, &["--strategy", "density"]);
+    assert_eq!(cached_videos(&output, 0), vec![0]);
+    assert_eq!(cached_videos(&output, 1), vec![0]);
+
+    let output = solve(&path, &["--strategy", "cluster"]);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Strategy: cluster"));
+    assert_eq!(cached_videos(&output, 0), vec![1]);
+    assert_eq!(cached_videos(&output, 1), vec![0]);
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn clustering_caches() {
+    // Endpoint 0 reaches caches 0 and 2, endpoint 1 cache 1 and endpoint 2 caches 3 and 1, so the
+    // caches form two groups that share no endpoint. Cache 4 is reached by no endpoint at all.
+    let input = Input::new(
+        vec![Video { size: 10 }],
+        vec![
+            Endpoint {
+                latency: 1000,
+                cache_connections: vec![(0, 100), (2, 200)],
+            },
+            Endpoint {
+                latency: 1000,
+                cache_connections: vec![(1, 100)],
+            },
+            Endpoint {
+                latency: 1000,
+                cache_connections: vec![(3, 100), (1, 300)],
+            },
+        ],
+        vec![Cache {}, Cache {}, Cache {}, Cache {}, Cache {}],
+        100,
+        vec![],
+    );
+    assert_eq!(
+        cache_clusters(&input),
+        vec![vec![0, 2], vec![1, 3], vec![4]]
+    );
+
+    let path = write_input(
+        "clusters",
+        "1 3 0 5 100\n10\n1000 2\n0 100\n2 200\n1000 1\n1 100\n1000 2\n3 100\n1 300\n",
+    );
+    let output = solve(&path, &["--verbose"]);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Cache clusters: 3 (2 2 1)\n"));
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn auto_picks_knapsack_for_large_videos() {
+    // The average video fills half a cache, where packing each cache tightly pays off
+    let path = write_input(
+        "auto-knapsack",
+        "3 1 3 2 100\n40 50 60\n1000 2\n0 100\n1 100\n0 0 10\n1 0 10\n2 0 10\n",
+    );
+    let summary =
+        String::from_utf8_lossy(&solve(&path, &["--strategy", "auto", "--verbose"]).stderr)
+            .into_owned();
+    assert!(
+        summary.contains(
+            "Recommended strategy: knapsack (the average video fills 50.00% of a cache, endpoints \
+             reach 100.00% of the caches on average)\n"
+        ),
+        "{}",
+        summary
+    );
+    assert!(summary.contains("Strategy: knapsack\n"), "{}", summary);
+    fs::remove_file(path).unwrap();
+
+    // Small videos in caches most endpoints share are left to density
+    let path = write_input(
+        "auto-density",
+        &generate(&["1000", "20", "10", "1000", "100000", "5"]),
+    );
+    let summary =
+        String::from_utf8_lossy(&solve(&path, &["--strategy", "auto", "--verbose"]).stderr)
+            .into_owned();
+    assert!(
+        summary.contains("Recommended strategy: density (the average video fills 0.49% of a cache"),
+        "{}",
+        summary
+    );
+    assert!(summary.contains("Strategy: density\n"), "{}", summary);
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn limiting_videos_per_cache() {
+    let path = generated_input("max-videos");
+    let most_videos = |output: &Output| {
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .skip(1)
+            .map(|line| line.split(' ').count() - 1)
+            .max()
+            .unwrap()
+    };
+    // Without a limit the caches hold many more than a few videos each
+    assert!(most_videos(&solve(&path, &[])) > 3);
+    for strategy in [
+        "greedy", "sorted", "coverage", "cluster", "density", "knapsack",
+    ] {
+        for max in ["1", "3"] {
+            let output = solve(
+                &path,
+                &[
+                    "--strategy",
+                    strategy,
+                    "--max-videos-per-cache",
+                    max,
+                    "--assert-valid",
+                ],
+            );
+            assert!(output.status.success(), "{} failed", strategy);
+            assert_eq!(most_videos(&output).to_string(), max, "{}", strategy);
+        }
+    }
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn placing_from_outside() {
+    // The endpoint reaches cache 0 in 100ms and cache 1 in 200ms, both of 100MB
+    let input = Input::new(
+        vec![Video { size: 100 }, Video { size: 60 }, Video { size: 30 }],
+        vec![Endpoint {
+            latency: 1000,
+            cache_connections: vec![(0, 100), (1, 200)],
+        }],
+        vec![Cache {}, Cache {}],
+        100,
+        vec![],
+    );
+    let mut state = State::new(&input);
+    assert_eq!(state.eligible_caches(1), vec![0, 1]);
+    assert_eq!(state.best_cache_for(0, 1), Some(0));
+
+    // A full cache takes nothing more, so video 1 can only go to the slower cache
+    state.insert_video_in_cache(0, 0);
+    assert_eq!(state.eligible_caches(1), vec![1]);
+    assert_eq!(state.eligible_caches(2), vec![1]);
+    assert_eq!(state.best_cache_for(0, 1), Some(1));
+
+    // With 40MB left cache 1 still takes video 2, but not another copy of video 1
+    state.insert_video_in_cache(1, 1);
+    assert_eq!(state.eligible_caches(1), Vec::<usize>::new());
+    assert_eq!(state.best_cache_for(0, 1), None);
+    assert_eq!(state.eligible_caches(2), vec![1]);
+    assert_eq!(state.best_cache_for(0, 2), Some(1));
+
+    // With 10MB left in cache 1, no cache is eligible for any of the videos
+    state.insert_video_in_cache(1, 2);
+    for video_id in 0..3 {
+        assert_eq!(state.eligible_caches(video_id), Vec::<usize>::new());
+        assert_eq!(state.best_cache_for(0, video_id), None);
+    }
+}
+
+#[test]
+fn custom_ranking() {
+    // Video 1 saves more per megabyte, video 0 more in all, and only one of them fits
+    let path = write_input(
+        "rank",
+        "2 1 2 1 100\n100 10\n1000 1\n0 100\n0 0 100\n1 0 20\n",
+    );
+    assert_eq!(cached_videos(&solve(&path, &[]), 0), vec![1]);
+    assert_eq!(
+        cached_videos(&solve(&path, &["--rank", "density"]), 0),
+        vec![1]
+    );
+    assert_eq!(
+        cached_videos(&solve(&path, &["--rank", "gain"]), 0),
+        vec![0]
+    );
+
+    let output = solve(&path, &["--rank", "size"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("unknown rank 'size'"));
+    fs::remove_file(path).unwrap();
+
+    // Video 0 saves endpoint 0 900ms through cache 0 and 800ms through cache 1, which endpoint 1
+    // reaches too. Ranked up front, its copy in cache 1 still looks worth 809000ms and beats video
+    // 1's 450000ms, but once cache 0 holds it the copy only saves endpoint 1's 10 requests.
+    let path = write_input(
+        "rank-again",
+        "2 2 3 2 100\n100 100\n1000 2\n0 100\n1 200\n1000 1\n1 100\n0 0 1000\n0 1 10\n1 1 500\n",
+    );
+    let output = solve(&path, &[]);
+    assert_eq!(cached_videos(&output, 1), vec![0]);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Total Saved: 909000ms"));
+    for rank in ["gain", "gain-squared"] {
+        let output = solve(&path, &["--rank", rank]);
+        assert_eq!(cached_videos(&output, 0), vec![0], "{}", rank);
+        assert_eq!(cached_videos(&output, 1), vec![1], "{}", rank);
+        assert!(String::from_utf8_lossy(&output.stderr).contains("Total Saved: 1350000ms"));
+    }
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn genetic_improves_on_density() {
+    let path = generated_input("genetic");
+    let score_of = |output: &Output| -> u64 { score(output)["Score: ".len()..].parse().unwrap() };
+
+    let density = solve(&path, &["--strategy", "density"]);
+    // Crossover keeps every child within the cache size, which --assert-valid checks
+    let genetic = solve(&path, &["--strategy", "genetic", "--assert-valid"]);
+    assert!(genetic.status.success());
+    assert!(
+        score_of(&genetic) > score_of(&density),
+        "{} <= {}",
+        score(&genetic),
+        score(&density)
+    );
+    // The best individual is a child rather than the density solution the population started from
+    assert_ne!(genetic.stdout, density.stdout);
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn tracking_marginal_gains() {
+    // Endpoint 0 gets video 0 from cache 0, after which cache 1 only saves endpoint 1's 100ms,
+    // though its density still counts endpoint 0's savings through it
+    let path = write_input(
+        "marginal",
+        "1 2 2 2 50\n50\n1000 2\n0 100\n1 200\n1000 1\n1 900\n0 0 1000\n0 1 100\n",
+    );
+    let output = solve(&path, &["--track-marginal"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains(
+            "Misranked: cache 1 video 0 (score 16200) predicted 810000ms, saved 10000ms\n"
+        ),
+        "{}",
+        stderr
+    );
+    assert!(!stderr.contains("Misranked: cache 0"));
+    assert!(stderr.contains("Misranked 1 of 2 placements"));
+
+    // Without the flag nothing is measured
+    let output = solve(&path, &[]);
+    assert!(!String::from_utf8_lossy(&output.stderr).contains("Misranked"));
+
+    let output = solve(&path, &["--track-marginal", "--strategy", "greedy"]);
+    assert_eq!(output.status.code(), Some(1));
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn epsilon_greedy() {
+    let path = generated_input("epsilon");
+
+    // Never exploring, every trial makes the deterministic placements
+    let deterministic = solve(&path, &[]);
+    for args in [
+        &["--epsilon", "0"][..],
+        &["--epsilon", "0", "--trials", "3", "--seed", "7"],
+    ] {
+        let output = solve(&path, args);
+        assert_eq!(output.stdout, deterministic.stdout, "{:?}", args);
+        assert_eq!(score(&output), score(&deterministic));
+    }
+
+    // Exploring is reproducible for a given seed
+    let args = ["--epsilon", "0.5", "--seed", "3"];
+    let first = solve(&path, &args);
+    assert_ne!(first.stdout, deterministic.stdout);
+    assert_eq!(solve(&path, &args).stdout, first.stdout);
+
+    let output = solve(&path, &["--trials", "3"]);
+    assert_eq!(output.status.code(), Some(1));
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn epsilon_window() {
+    let path = generated_input("window");
+
+    // A window of one leaves only the best candidate to pick, whatever the seed
+    let deterministic = solve(&path, &[]);
+    for seed in ["0", "3", "11"] {
+        let args = [
+            "--epsilon",
+            "1",
+            "--window",
+            "1",
+            "--seed",
+            seed,
+            "--trials",
+            "2",
+        ];
+        let output = solve(&path, &args);
+        assert_eq!(output.stdout, deterministic.stdout, "{:?}", args);
+        assert_eq!(score(&output), score(&deterministic));
+    }
+
+    // A wider window explores
+    let output = solve(&path, &["--epsilon", "1", "--window", "4", "--seed", "3"]);
+    assert_ne!(output.stdout, deterministic.stdout);
+
+    for args in [
+        &["--window", "4"][..],
+        &["--epsilon", "0.5", "--window", "0"],
+    ] {
+        assert_eq!(solve(&path, args).status.code(), Some(1), "{:?}", args);
+    }
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn storage_budget() {
+    let path = generated_input("budget");
+    let sizes: Vec<u64> = fs::read_to_string(&path)
+        .unwrap()
+        .lines()
+        .nth(1)
+        .unwrap()
+        .split_whitespace()
+        .map(|size| size.parse().unwrap())
+        .collect();
+    // The megabytes placed across all caches
+    let placed = |output: &Output| -> u64 {
+        (0..20)
+            .flat_map(|cache_id| cached_videos(output, cache_id))
+            .map(|video_id| sizes[video_id])
+            .sum()
+    };
+    assert!(placed(&solve(&path, &[])) > 2000);
+
+    for strategy in [
+        "density", "greedy", "sorted", "coverage", "cluster", "knapsack", "genetic", "video",
+    ] {
+        let output = solve(
+            &path,
+            &["--strategy", strategy, "--budget", "2000", "--assert-valid"],
+        );
+        assert!(output.status.success(), "{}", strategy);
+        let placed = placed(&output);
+        assert!(placed <= 2000, "{} placed {}MB", strategy, placed);
+        assert!(placed > 0, "{}", strategy);
+    }
+
+    for args in [
+        &["--budget", "lots"][..],
+        &["--budget", "100", "--load", "x"],
+    ] {
+        assert_eq!(solve(&path, args).status.code(), Some(1), "{:?}", args);
+    }
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn shuffling_endpoints() {
+    let path = generated_input("shuffle");
+    // The solution and score of a single shuffled order
+    let shuffled = |seed: u64| {
+        let output = solve(
+            &path,
+            &[
+                "--shuffle-endpoints",
+                "--seed",
+                &seed.to_string(),
+                "--assert-valid",
+            ],
+        );
+        assert!(output.status.success(), "seed {}", seed);
+        let score: u64 = score(&output)["Score: ".len()..].parse().unwrap();
+        (output.stdout, score)
+    };
+
+    let orders: Vec<(Vec<u8>, u64)> = (0..4).map(shuffled).collect();
+    assert!(
+        orders.iter().any(|order| order.0 != orders[0].0),
+        "every order gave the same solution"
+    );
+
+    // The best order is kept, and its seed reproduces it on its own
+    let output = solve(
+        &path,
+        &["--shuffle-endpoints", "--seed", "0", "--trials", "4"],
+    );
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let best = orders.iter().map(|order| order.1).max().unwrap();
+    let seed = orders.iter().position(|order| order.1 == best).unwrap();
+    assert!(
+        stderr.contains(&format!(
+            "Shuffled endpoints: best of 4 trials with seed {}\n",
+            seed
+        )),
+        "{}",
+        stderr
+    );
+    assert_eq!(score(&output), format!("Score: {}", best));
+    assert_eq!(output.stdout, orders[seed].0);
+
+    let output = solve(&path, &["--strategy", "endpoint", "--seed", "1"]);
+    assert_eq!(output.status.code(), Some(1));
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn exact_optimum() {
+    let sizes = [30, 40, 50, 60];
+    // Each endpoint's datacenter latency and its (cache, latency) connections
+    let endpoints: [(u64, &[(usize, u64)]); 2] =
+        [(1000, &[(0, 100), (1, 300)]), (500, &[(1, 100)])];
+    // (video, endpoint, amount)
+    let requests = [
+        (0, 0, 100),
+        (1, 0, 200),
+        (2, 0, 300),
+        (3, 1, 400),
+        (2, 1, 100),
+        (1, 1, 50),
+    ];
+    let path = write_input(
+        "exact",
+        "4 2 6 2 100\n30 40 50 60\n1000 2\n0 100\n1 300\n500 1\n1 100\n\
+         0 0 100\n1 0 200\n2 0 300\n3 1 400\n2 1 100\n1 1 50\n",
+    );
+
+    // Every pair of video subsets that fit in the two caches of 100MB
+    let mut best = 0;
+    for a in 0..16usize {
+        for b in 0..16usize {
+            let subsets = [a, b];
+            let fits = subsets.iter().all(|&subset| {
+                (0..4)
+                    .filter(|&video| subset & 1 << video != 0)
+                    .map(|video| sizes[video])
+                    .sum::<u64>()
+                    <= 100
+            });
+            if !fits {
+                continue;
+            }
+            let mut saved = 0;
+            let mut total = 0;
+            for &(video, endpoint, amount) in requests.iter() {
+                let (latency, connections) = endpoints[endpoint];
+                let fastest = connections
+                    .iter()
+                    .filter(|&&(cache, _)| subsets[cache] & 1 << video != 0)
+                    .map(|&(_, cache_latency)| cache_latency)
+                    .min()
+                    .unwrap_or(latency);
+                saved += (latency - fastest) * amount;
+                total += amount;
+            }
+            best = best.max(saved * 1000 / total);
+        }
+    }
+
+    let output = solve(&path, &["--strategy", "exact", "--assert-valid"]);
+    assert!(output.status.success());
+    assert_eq!(score(&output), format!("Score: {}", best));
+    // Density misses the optimum here
+    assert_ne!(score(&solve(&path, &[])), format!("Score: {}", best));
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn saving_scores() {
+    let path = generated_input("scores");
+    let scores = path.with_extension("scores");
+    let scores_path = scores.to_str().unwrap();
+
+    let saved = solve(&path, &["--prune", "5", "--save-scores", scores_path]);
+    assert!(saved.status.success());
+    // Loading the scores places the same videos as computing them
+    let loaded = solve(&path, &["--prune", "5", "--load-scores", scores_path]);
+    assert!(loaded.status.success());
+    assert_eq!(loaded.stdout, saved.stdout);
+    assert_eq!(score(&loaded), score(&saved));
+
+    // Scores computed with other settings or for another input are refused
+    let output = solve(&path, &["--load-scores", scores_path]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("the scores were computed for a different prune threshold"));
+    let other = write_input("scores-other", EXAMPLE);
+    let output = solve(&other, &["--prune", "5", "--load-scores", scores_path]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("the scores were computed for a different input"));
+
+    fs::remove_file(path).unwrap();
+    fs::remove_file(other).unwrap();
+    fs::remove_file(scores).unwrap();
+}
+
+#[test]
+fn aggregation_is_independent_of_threads() {
+    // The density scores are summed per cache and video by a parallel fold, whose partial sums
+    // depend on how the request descriptions are split between threads. The saved scores hold the
+    // aggregate itself, which must come out the same from one thread as from many.
+    let path = write_input(
+        "threads",
+        &generate(&["2000", "50", "50", "20000", "10000", "1"]),
+    );
+    let aggregate = |threads: &str| {
+        let scores = path.with_extension(format!("{}.scores", threads));
+        let output = solve(
+            &path,
+            &[
+                "--threads",
+                threads,
+                "--save-scores",
+                scores.to_str().unwrap(),
+            ],
+        );
+        assert!(output.status.success(), "{} threads failed", threads);
+        let saved = fs::read_to_string(&scores).unwrap();
+        fs::remove_file(scores).unwrap();
+        (saved, output)
+    };
+
+    let (serial, serial_output) = aggregate("1");
+    for threads in ["2", "4", "8"] {
+        let (parallel, output) = aggregate(threads);
+        assert!(
+            parallel == serial,
+            "{} threads aggregate differently",
+            threads
+        );
+        assert_eq!(output.stdout, serial_output.stdout, "{} threads", threads);
+        assert_eq!(score(&output), score(&serial_output));
+    }
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn reserving_room_for_small_videos() {
+    // Bimodal sizes: a 70MB video saving 1000ms on 71 requests, 1014ms per megabyte, and five of
+    // 20MB saving as much on 20 requests each, 1000ms per megabyte. Density places the large one
+    // first and then only one of the small ones fits, leaving 10MB unused.
+    let path = write_input(
+        "bimodal",
+        "6 1 6 1 100\n70 20 20 20 20 20\n1000 1\n0 0\n\
+         0 0 71\n1 0 20\n2 0 20\n3 0 20\n4 0 20\n5 0 20\n",
+    );
+    let density = solve(&path, &[]);
+    assert_eq!(cached_videos(&density, 0), vec![0, 1]);
+    // 91000ms saved over 171 requests
+    assert_eq!(score(&density), "Score: 532163");
+
+    // Reserving the cache for videos up to a quarter of it first fills it with the small ones
+    let reserved = solve(&path, &["--reserve", "0.25"]);
+    assert!(reserved.status.success());
+    assert_eq!(cached_videos(&reserved, 0), vec![1, 2, 3, 4, 5]);
+    // 100000ms saved over 171 requests
+    assert_eq!(score(&reserved), "Score: 584795");
+
+    // Below the smallest video nothing is reserved and the second phase is plain density
+    let output = solve(&path, &["--reserve", "0.1"]);
+    assert_eq!(output.stdout, density.stdout);
+    assert_eq!(score(&output), score(&density));
+    fs::remove_file(path).unwrap();
+}